@@ -0,0 +1,186 @@
+use crate::installed_mods;
+use crate::wtf::{self, WtfFilter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File in each character's WTF folder that records which addons are enabled
+const FILE_NAME: &str = "AddOns.txt";
+
+/// The addons of one character as its AddOns.txt has them
+#[derive(Debug, Clone)]
+pub struct CharacterAddons {
+    pub account: String,
+    pub realm: String,
+    pub character: String,
+    /// Installed folders and whether each is enabled. Folders missing from AddOns.txt are
+    /// enabled, as WoW loads new addons for every character.
+    pub addons: Vec<(String, bool)>,
+}
+
+/// Reads the enabled addons of every character matching the filter
+pub fn list_characters(
+    filter: &WtfFilter,
+) -> Result<Vec<CharacterAddons>, Box<dyn std::error::Error>> {
+    let installed = installed_mods::installed_folders()?;
+    let mut characters = Vec::new();
+    for path in wtf::find_characters(filter)? {
+        let states = read_states(&path.join(FILE_NAME))?;
+        let name = |path: Option<&Path>| {
+            path.and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        characters.push(CharacterAddons {
+            account: name(path.parent().and_then(Path::parent)),
+            realm: name(path.parent()),
+            character: name(Some(&path)),
+            addons: installed
+                .iter()
+                .map(|folder| {
+                    let enabled = states
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(folder))
+                        .is_none_or(|(_, enabled)| *enabled);
+                    (folder.clone(), enabled)
+                })
+                .collect(),
+        });
+    }
+
+    Ok(characters)
+}
+
+/// Checks that addon folders are installed, returning them as they are named on disk
+pub fn installed_names(folders: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let installed = installed_mods::installed_folders()?;
+    let mut names = Vec::new();
+    let mut unknown = Vec::new();
+    for folder in folders {
+        match installed
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(folder.trim()))
+        {
+            Some(name) => names.push(name.clone()),
+            None => unknown.push(folder.trim()),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(format!("Not installed in AddOns: {}", unknown.join(", ")).into());
+    }
+
+    Ok(names)
+}
+
+/// Enables or disables addon folders in the AddOns.txt of every character matching the
+/// filter, keeping the lines of other addons. Returns the files that were written.
+pub fn set_enabled(
+    filter: &WtfFilter,
+    folders: &[String],
+    enabled: bool,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let characters = wtf::find_characters(filter)?;
+    if characters.is_empty() {
+        return Err(
+            "No characters match, log in with a character once so WoW creates its folder".into(),
+        );
+    }
+
+    let mut written = Vec::new();
+    for character in characters {
+        let path = character.join(FILE_NAME);
+        let mut states = read_states(&path)?;
+        for folder in folders {
+            match states
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(folder))
+            {
+                Some((_, state)) => *state = enabled,
+                None => states.push((folder.clone(), enabled)),
+            }
+        }
+        write_states(&path, &states)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// The folders listed in an AddOns.txt and whether each is enabled, in file order
+fn read_states(path: &Path) -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, state)| {
+            (
+                name.trim().to_string(),
+                state.trim().eq_ignore_ascii_case("enabled"),
+            )
+        })
+        .filter(|(name, _)| !name.is_empty())
+        .collect())
+}
+
+fn write_states(path: &Path, states: &[(String, bool)]) -> Result<(), Box<dyn std::error::Error>> {
+    let content: String = states
+        .iter()
+        .map(|(name, enabled)| {
+            format!(
+                "{}: {}\n",
+                name,
+                if *enabled { "enabled" } else { "disabled" }
+            )
+        })
+        .collect();
+    fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn read_states_parses_lines_loosely() {
+        let dir = TempDir::new("addons-txt-parse");
+        let path = dir.join(FILE_NAME);
+        fs::write(
+            &path,
+            "Details: enabled\n  WeakAuras :DISABLED\r\nnot an addon line\n: enabled\nPlater: off\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_states(&path).unwrap(),
+            vec![
+                ("Details".to_string(), true),
+                ("WeakAuras".to_string(), false),
+                ("Plater".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_states_of_missing_file_is_empty() {
+        let dir = TempDir::new("addons-txt-missing");
+        assert!(read_states(&dir.join(FILE_NAME)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn write_states_round_trips() {
+        let dir = TempDir::new("addons-txt-round-trip");
+        let path = dir.join(FILE_NAME);
+        let states = vec![("Details".to_string(), true), ("Bar".to_string(), false)];
+        write_states(&path, &states).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Details: enabled\nBar: disabled\n"
+        );
+        assert_eq!(read_states(&path).unwrap(), states);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::manifest::{format_timestamp, now_timestamp, Manifest, ManifestEntry};
+use crate::providers::{self, DEFAULT_PROVIDER};
+use crate::{curseforge_api, download_cache};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An archived copy of an addon's folders taken before they were overwritten
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub mod_id: u32,
+    /// File id of the archived version, 0 when addown did not install it
+    pub file_id: u32,
+    pub created_at: u64,
+    pub path: PathBuf,
+    /// Manifest entry of the archived version, if it was tracked
+    pub entry: Option<ManifestEntry>,
+}
+
+fn mod_backups_path(mod_id: u32) -> PathBuf {
+    Path::new(&CurseForgeConfig::get().backups_path()).join(mod_id.to_string())
+}
+
+/// Archives the folders currently installed for `mod_id` that an install of `new_dirs` is
+/// about to replace. Returns `None` when nothing is installed yet or backups are turned off.
+pub fn backup_installed(
+    mod_id: u32,
+    new_dirs: &[String],
+) -> Result<Option<Backup>, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    if config.backup_count == 0 {
+        return Ok(None);
+    }
+    let manifest = Manifest::load()?;
+    let entry = manifest.find(mod_id).cloned();
+
+    let mut dirs: Vec<String> = entry
+        .as_ref()
+        .map(|entry| entry.directories.clone())
+        .unwrap_or_default();
+    for dir in new_dirs {
+        if !dirs.contains(dir) {
+            dirs.push(dir.clone());
+        }
+    }
+    // dev links are left out, their working copy is not Addown's to archive
+    dirs.retain(|dir| {
+        fs::symlink_metadata(Path::new(&config.addons_path()).join(dir))
+            .is_ok_and(|metadata| metadata.is_dir())
+    });
+    if dirs.is_empty() {
+        return Ok(None);
+    }
+
+    let backup_dir = mod_backups_path(mod_id);
+    fs::create_dir_all(&backup_dir)?;
+    let file_id = entry.as_ref().map(|entry| entry.file_id).unwrap_or(0);
+    let created_at = now_timestamp();
+    // backups taken within the same second are numbered so none overwrites another
+    let mut path = backup_dir.join(format!("{}-{}.zip", file_id, created_at));
+    let mut sequence = 0;
+    while path.exists() {
+        sequence += 1;
+        path = backup_dir.join(format!("{}-{}-{}.zip", file_id, created_at, sequence));
+    }
+    curseforge_api::zip_dirs(&path, Path::new(&config.addons_path()), &dirs, &[])?;
+    if let Some(entry) = &entry {
+        fs::write(
+            path.with_extension("json"),
+            serde_json::to_string_pretty(entry)?,
+        )?;
+    }
+    eprintln!("Backed up {} to {}", dirs.join(", "), path.display());
+
+    prune_backups(mod_id, config.backup_count)?;
+
+    Ok(Some(Backup {
+        mod_id,
+        file_id,
+        created_at,
+        path,
+        entry,
+    }))
+}
+
+/// Lists the backups kept for an addon, newest first
+pub fn list_backups(mod_id: u32) -> Result<Vec<Backup>, Box<dyn std::error::Error>> {
+    let mut backups = Vec::new();
+    let files = match fs::read_dir(mod_backups_path(mod_id)) {
+        Ok(files) => files,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    for file in files {
+        let path = file?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("zip") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        // `<file id>-<created at>`, with `-<sequence>` for later backups in the same second
+        let parts: Vec<&str> = stem.splitn(3, '-').collect();
+        let (file_id, created_at, sequence) = match parts[..] {
+            [file_id, created_at] => (file_id, created_at, "0"),
+            [file_id, created_at, sequence] => (file_id, created_at, sequence),
+            _ => continue,
+        };
+        let (Ok(file_id), Ok(created_at), Ok(sequence)) =
+            (file_id.parse(), created_at.parse(), sequence.parse::<u32>())
+        else {
+            continue;
+        };
+        let entry = fs::read_to_string(path.with_extension("json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+
+        backups.push((
+            sequence,
+            Backup {
+                mod_id,
+                file_id,
+                created_at,
+                path,
+                entry,
+            },
+        ));
+    }
+
+    backups.sort_by_key(|(sequence, backup)| std::cmp::Reverse((backup.created_at, *sequence)));
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+/// Deletes all but the newest `keep` backups of an addon
+fn prune_backups(mod_id: u32, keep: usize) -> Result<(), Box<dyn std::error::Error>> {
+    for backup in list_backups(mod_id)?.into_iter().skip(keep) {
+        fs::remove_file(&backup.path)?;
+        fs::remove_file(backup.path.with_extension("json")).ok();
+    }
+
+    Ok(())
+}
+
+/// Restores a previous version of an addon from its backups, or from the download cache
+/// when `to_file_id` names a version that was never backed up. The version being replaced
+/// is itself backed up so a rollback can be undone.
+pub fn rollback(
+    mod_id: u32,
+    to_file_id: Option<u32>,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let backups = list_backups(mod_id)?;
+    let current = Manifest::load()?.find(mod_id).cloned();
+    if let Some(entry) = current
+        .as_ref()
+        .filter(|entry| !providers::has_remote(&entry.provider))
+    {
+        return Err(format!(
+            "{} was installed from {} and can't be rolled back",
+            entry.name, entry.file_name
+        )
+        .into());
+    }
+    // folders the mod is known to install, to check that a cached file really belongs to it
+    let mut known_dirs: Vec<String> = current
+        .iter()
+        .chain(backups.iter().filter_map(|backup| backup.entry.as_ref()))
+        .flat_map(|entry| entry.directories.iter().cloned())
+        .collect();
+    known_dirs.sort();
+    known_dirs.dedup();
+    let backup = match to_file_id {
+        Some(file_id) => backups.into_iter().find(|backup| backup.file_id == file_id),
+        None => backups.into_iter().next(),
+    };
+
+    let current_name = current
+        .as_ref()
+        .map(|entry| entry.name.clone())
+        .unwrap_or_default();
+    let current_provider = current
+        .as_ref()
+        .map(|entry| entry.provider.clone())
+        .unwrap_or_else(|| DEFAULT_PROVIDER.to_string());
+    let current_source_id = current.map(|entry| entry.provider_id()).unwrap_or_default();
+    let (mut entry, archive_path) = match (backup, to_file_id) {
+        (Some(backup), _) => {
+            eprintln!(
+                "Restoring backup from {}",
+                format_timestamp(backup.created_at)
+            );
+            let entry = backup.entry.unwrap_or(ManifestEntry {
+                mod_id,
+                name: current_name,
+                file_id: backup.file_id,
+                file_name: String::new(),
+                display_name: String::new(),
+                directories: Vec::new(),
+                installed_at: 0,
+                provider: current_provider,
+                source_id: current_source_id,
+                release_tag: None,
+                replaced: Vec::new(),
+            });
+            (entry, backup.path)
+        }
+        (None, Some(file_id)) => {
+            let cached = download_cache::find_file(&current_provider, file_id).ok_or(format!(
+                "No backup or cached download of file {} for mod {}",
+                file_id, mod_id
+            ))?;
+            // the cache doesn't record the mod, so the archive has to install at least one of
+            // the mod's folders
+            let dirs = curseforge_api::get_top_level_dirs(&cached.path.to_string_lossy())?;
+            if !dirs.iter().any(|dir| known_dirs.contains(dir)) {
+                return Err(format!(
+                    "Cached file {} ({}) does not belong to mod {}",
+                    file_id, cached.file_name, mod_id
+                )
+                .into());
+            }
+            eprintln!("Restoring cached {}", cached.file_name);
+            let entry = ManifestEntry {
+                mod_id,
+                name: current_name,
+                file_id,
+                file_name: cached.file_name.clone(),
+                display_name: cached.file_name,
+                directories: Vec::new(),
+                installed_at: 0,
+                provider: current_provider,
+                source_id: current_source_id,
+                release_tag: None,
+                replaced: Vec::new(),
+            };
+            (entry, cached.path)
+        }
+        (None, None) => return Err(format!("No backups found for mod {}", mod_id).into()),
+    };
+
+    entry.directories = curseforge_api::install_archive(&archive_path, mod_id, cancel)?;
+    entry.installed_at = now_timestamp();
+    let mut manifest = Manifest::load()?;
+    manifest.upsert(entry.clone());
+    manifest.save()?;
+
+    Ok(entry)
+}
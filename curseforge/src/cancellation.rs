@@ -0,0 +1,78 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Shared flag that long running commands check at points where they can stop safely.
+/// Ctrl+C in the cli loop cancels the token of the command that is currently running.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns an `Interrupted` error if the token has been cancelled
+    pub fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_cancelled() {
+            Err(Box::new(Interrupted))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Completes once the token is cancelled
+    pub async fn cancelled(&self) {
+        let notified = self.notify.notified();
+        tokio::pin!(notified);
+        // registered before checking the flag so a cancel in between still wakes it
+        notified.as_mut().enable();
+        if !self.is_cancelled() {
+            notified.await;
+        }
+    }
+
+    /// Awaits a future that can't check the token itself, such as a network request, and
+    /// drops it with an `Interrupted` error if the token is cancelled first
+    pub async fn run<T, E>(
+        &self,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, Box<dyn std::error::Error>>
+    where
+        E: Into<Box<dyn std::error::Error>>,
+    {
+        tokio::select! {
+            result = future => result.map_err(Into::into),
+            _ = self.cancelled() => Err(Box::new(Interrupted)),
+        }
+    }
+}
+
+/// Error returned by a pipeline that stopped because its token was cancelled
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Command was interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
@@ -1,13 +1,34 @@
-use crate::{curseforge_api, game_version, installed_mods, mod_table};
-use clap::{Parser, Subcommand};
+use crate::cancellation::{CancellationToken, Interrupted};
+use crate::completion::AddownHelper;
+use crate::config::CurseForgeConfig;
+use crate::history::CommandHistory;
+use crate::installed_mods::{ViewOptions, ViewSort};
+use crate::manifest::{format_api_date, format_timestamp, Manifest, ManifestEntry};
+use crate::migrate::Manager;
+use crate::models::Mod;
+use crate::output::{
+    ArchiveRow, CharacterAddonRow, GroupRow, InstallResult, ModDetails, OutputFormat, SearchHit,
+    SnapshotDiffRow,
+};
+use crate::profile::ImportMode;
+use crate::snapshot::SnapshotChange;
+use crate::updater::UpdatePlanEntry;
+use crate::wtf::WtfFilter;
+use crate::{
+    addons_txt, backup, completion, curseforge_api, download_cache, game_version, groups, html,
+    installed_mods, migrate, mod_table, output, profile, providers, snapshot, updater, wtf,
+};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use rustyline::error::ReadlineError;
-use rustyline::DefaultEditor;
+use rustyline::history::DefaultHistory;
+use rustyline::{CompletionType, Config, Editor};
 use std::io;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tokio::task::JoinHandle;
 
 #[derive(Debug)]
 pub enum InputEvent {
@@ -22,6 +43,7 @@ pub struct CtrlCState {
     showing_message: bool,
     command_in_progress: bool,
     interrupt_command: bool,
+    cancel_token: CancellationToken,
 }
 
 impl CtrlCState {
@@ -31,6 +53,7 @@ impl CtrlCState {
             showing_message: false,
             command_in_progress: false,
             interrupt_command: false,
+            cancel_token: CancellationToken::new(),
         }
     }
 }
@@ -46,29 +69,66 @@ impl Default for CtrlCState {
 #[command(name = "Addown")]
 // description of the program
 #[command(about = "A CLI WoW Addon Manager", long_about = None)]
-struct Addown {
-    // this field will hold the subcommands
+pub struct Addown {
+    // this field will hold the subcommands, the interactive cli starts when there is none
     #[command(subcommand)]
-    command: Commands,
+    pub command: Option<Commands>,
+    /// How to print results: table, json or csv
+    #[arg(short = 'o', long = "output", global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
 }
 
 #[derive(Subcommand)]
-enum Commands {
+pub enum Commands {
     /// Initialize wow installation
     Init,
     /// View installed addons
-    View,
+    View {
+        /// Order of the addons
+        #[arg(short = 's', long = "sort", value_enum, default_value_t = ViewSort::Name)]
+        sort: ViewSort,
+        /// Only show addons whose name, folder, notes or id match this text
+        #[arg(short = 'f', long = "filter")]
+        filter: Option<String>,
+        /// Only show addons with a newer version available
+        #[arg(long = "outdated")]
+        outdated: bool,
+        /// Only show addons that could not be matched to a CurseForge project
+        #[arg(long = "unknown")]
+        unknown: bool,
+    },
     /// Search for addons
     Search {
         /// The exact name of the addon to search for
         #[arg(short = 'f', long = "filter")]
         name: Option<String>,
+        /// Where to search
+        #[arg(long = "source", default_value = providers::DEFAULT_PROVIDER, value_parser = providers::PROVIDERS)]
+        source: String,
+    },
+    /// Show the details of an addon
+    Info {
+        /// The id of the addon
+        #[arg(short = 'i', long = "id")]
+        id: u32,
     },
     /// Get addons
     Get {
         /// The id(s) of addons to get
         #[arg(short = 'i', long = "ids")]
         ids: Option<String>,
+        /// Where to get the addons from
+        #[arg(long = "source", default_value = providers::DEFAULT_PROVIDER, value_parser = providers::PROVIDERS)]
+        source: String,
+        /// GitHub repositories (owner/repo, comma-separated) to install the latest release of
+        #[arg(long = "github", conflicts_with = "ids")]
+        github: Option<String>,
+        /// An addon zip on disk to install
+        #[arg(long = "file", conflicts_with_all = ["ids", "github", "path"])]
+        file: Option<PathBuf>,
+        /// An addon folder on disk to install, or a folder of addon folders
+        #[arg(long = "path", conflicts_with_all = ["ids", "github"])]
+        path: Option<PathBuf>,
     },
     /// Delete addons
     Delete {
@@ -88,10 +148,238 @@ enum Commands {
         #[arg(short = 'f', long = "force")]
         force: bool,
     },
-    /// Update all addons
+    /// Restore a previous version of an addon
+    Rollback {
+        /// The id of the addon to roll back
+        #[arg(short = 'i', long = "id")]
+        id: u32,
+        /// The file id to restore, defaults to the most recent backup
+        #[arg(long = "to")]
+        to: Option<u32>,
+        /// List the available backups instead of restoring one
+        #[arg(short = 'l', long = "list")]
+        list: bool,
+    },
+    /// Symlink an addon working copy into AddOns for development
+    Link {
+        /// The working copy, an addon folder or a folder of addon folders
+        path: PathBuf,
+    },
+    /// Remove a linked working copy and restore the release it replaced
+    Unlink {
+        /// The linked working copy's path, addon name or id
+        target: String,
+    },
+    /// Write the installed addons as a profile that import can reproduce
+    Export {
+        /// File to write the profile to, printed when not given
+        file: Option<PathBuf>,
+        /// Ids of addons (comma-separated) to keep at their installed file on import
+        #[arg(long = "pin")]
+        pin: Option<String>,
+    },
+    /// Install the addons of a profile written by export, or take over another manager's addons
+    Import {
+        /// The profile to import, or the state file of the manager given with --from
+        file: PathBuf,
+        /// Install the exact files in the profile
+        #[arg(long = "exact", conflicts_with = "latest")]
+        exact: bool,
+        /// Install the newest files for the installed game version, the default
+        #[arg(long = "latest")]
+        latest: bool,
+        /// Record the addons another manager installed instead, without reinstalling them
+        #[arg(long = "from", conflicts_with_all = ["exact", "latest"])]
+        from: Option<Manager>,
+    },
+    /// Save, restore and compare copies of the whole AddOns folder
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommands,
+    },
+    /// Back up and restore addon settings (SavedVariables) in the WTF folder
+    Wtf {
+        #[command(subcommand)]
+        action: WtfCommands,
+    },
+    /// Named groups of addons that are enabled and disabled together
+    Group {
+        #[command(subcommand)]
+        action: GroupCommands,
+    },
+    /// See and change which addons each character loads (WTF AddOns.txt)
+    Character {
+        #[command(subcommand)]
+        action: CharacterCommands,
+    },
+    /// Manage the local download cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommands,
+    },
+    /// Open the full screen interface
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Print a shell completion script
+    Completions {
+        /// The shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Exit the interactive cli
     Exit,
 }
 
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Archive the AddOns folder and the manifest
+    Create {
+        /// Name of the snapshot, defaults to the current date and time
+        name: Option<String>,
+    },
+    /// List saved snapshots
+    List,
+    /// Replace the AddOns folder with a snapshot, saving the current one as `pre-restore-<time>`
+    Restore {
+        /// Name of the snapshot to restore
+        name: String,
+    },
+    /// Show which addons changed between two snapshots
+    Diff {
+        /// The older snapshot
+        a: String,
+        /// The newer snapshot
+        b: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WtfCommands {
+    /// Archive account and character SavedVariables
+    Backup {
+        /// Name of the backup, defaults to the current date and time
+        name: Option<String>,
+        /// Only back up this account
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// Only back up this character, as Name or Realm/Name
+        #[arg(short = 'c', long = "character")]
+        character: Option<String>,
+    },
+    /// List saved WTF backups
+    List,
+    /// Restore SavedVariables from a backup. WoW should be closed first.
+    Restore {
+        /// Name of the backup to restore
+        name: String,
+        /// Only restore this account
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// Only restore this character, as Name or Realm/Name
+        #[arg(short = 'c', long = "character")]
+        character: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GroupCommands {
+    /// List groups and their addons
+    List,
+    /// Add installed addons to a group, creating it if needed
+    Add {
+        /// Name of the group
+        name: String,
+        /// The id(s) of addons to add
+        #[arg(short = 'i', long = "ids")]
+        ids: String,
+    },
+    /// Remove addons from a group, or the whole group without --ids
+    Remove {
+        /// Name of the group
+        name: String,
+        /// The id(s) of addons to remove
+        #[arg(short = 'i', long = "ids")]
+        ids: Option<String>,
+    },
+    /// Move a group's folders back into AddOns, or enable them for characters
+    Enable {
+        /// Name of the group
+        name: String,
+        /// Enable them in AddOns.txt for this account's characters instead
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// Enable them in AddOns.txt for this character instead, as Name or Realm/Name
+        #[arg(short = 'c', long = "character")]
+        character: Option<String>,
+    },
+    /// Move a group's folders out of AddOns, or disable them for characters
+    Disable {
+        /// Name of the group
+        name: String,
+        /// Disable them in AddOns.txt for this account's characters instead
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// Disable them in AddOns.txt for this character instead, as Name or Realm/Name
+        #[arg(short = 'c', long = "character")]
+        character: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CharacterCommands {
+    /// List the addons each character has enabled
+    List {
+        /// Only this account's characters
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// Only this character, as Name or Realm/Name
+        #[arg(short = 'c', long = "character")]
+        character: Option<String>,
+    },
+    /// Enable addon folders for a character or all characters
+    Enable {
+        /// Addon folders to enable (comma-separated)
+        folders: String,
+        /// Only this account's characters
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// The character, as Name or Realm/Name
+        #[arg(short = 'c', long = "character", required_unless_present = "all")]
+        character: Option<String>,
+        /// Every character
+        #[arg(long = "all", conflicts_with = "character")]
+        all: bool,
+    },
+    /// Disable addon folders for a character or all characters
+    Disable {
+        /// Addon folders to disable (comma-separated)
+        folders: String,
+        /// Only this account's characters
+        #[arg(short = 'a', long = "account")]
+        account: Option<String>,
+        /// The character, as Name or Realm/Name
+        #[arg(short = 'c', long = "character", required_unless_present = "all")]
+        character: Option<String>,
+        /// Every character
+        #[arg(long = "all", conflicts_with = "character")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// List cached addon archives
+    List,
+    /// Remove cached archives that have not been used recently
+    Prune {
+        /// Age such as 30d, 12h or 2w
+        #[arg(long = "older-than")]
+        older_than: String,
+    },
+    /// Show the total size of the cache
+    Size,
+}
+
 /// The main cli parsing loop
 pub async fn main_loop(
     ctrl_c_state: Arc<Mutex<CtrlCState>>,
@@ -131,12 +419,20 @@ pub async fn main_loop(
                         full_args.extend(args.iter().map(|s| s.as_str()));
 
                         match Addown::try_parse_from(full_args) {
-                            Ok(cli) => {
+                            Ok(Addown { command: Some(command), output }) => {
 
                                 // Spawn command execution in separate task so main loop stays responsive
                                 let ctrl_c_state_clone = ctrl_c_state.clone();
+                                let (command_input_tx, command_input_rx) =
+                                    tokio::sync::mpsc::unbounded_channel();
                                 let mut command_handle = tokio::spawn(async move {
-                                    execute_command(cli.command, &ctrl_c_state_clone).await
+                                    execute_command(
+                                        command,
+                                        output,
+                                        &ctrl_c_state_clone,
+                                        command_input_rx,
+                                    )
+                                    .await
                                 });
 
                                 // Wait for either command completion or keep processing other events
@@ -154,7 +450,7 @@ pub async fn main_loop(
                                                     }
                                                 }
                                                 Ok(Err(e)) => {
-                                                    if e.to_string().contains("interrupted") {
+                                                    if e.is::<Interrupted>() {
                                                         print!("\r\x1b[2K\x1b[1A\x1b[2K");
                                                         io::stdout().flush().unwrap();
                                                         println!("\x1b[1ACommand was interrupted");
@@ -179,14 +475,16 @@ pub async fn main_loop(
                                                     let mut state = ctrl_c_state.lock().unwrap();
                                                     if state.command_in_progress {
                                                         state.interrupt_command = true;
+                                                        state.cancel_token.cancel();
                                                         // Continue loop to wait for command to actually stop
                                                     }
                                                 }
-                                                Some(InputEvent::Command(_line)) => {
-                                                    // User tried to run another command while one is running
+                                                Some(InputEvent::Command(line)) => {
+                                                    // Lines typed while a command runs go to the command, e.g. to
+                                                    // page through a table. Commands that don't read input drop them.
                                                     print!("\r\x1b[2K\x1b[1A");
                                                     io::stdout().flush().unwrap();
-                                                    //println!("⚠️ Command '{}' ignored - another command is still running. Press Ctrl+C to interrupt it.", line.trim());
+                                                    command_input_tx.send(line).ok();
                                                     continue;
                                                 }
                                                 Some(InputEvent::Exit) => {
@@ -202,6 +500,12 @@ pub async fn main_loop(
                                     }
                                 }
                             }
+                            Ok(Addown { command: None, .. }) => {
+                                print!("\r\x1b[2K\x1b[?25l");
+                                show_help();
+                                print!("\x1b[32maddown>\x1b[97m\x1b[?25h ");
+                                io::stdout().flush().unwrap();
+                            }
                             Err(e) => {
                                 print!("\r\x1b[2K\x1b[?25l");
                                 io::stdout().flush().unwrap();
@@ -239,106 +543,1080 @@ pub async fn main_loop(
 
 pub async fn execute_command(
     command: Commands,
+    output: OutputFormat,
     ctrl_c_state: &Arc<Mutex<CtrlCState>>,
+    mut input: UnboundedReceiver<String>,
 ) -> anyhow::Result<bool> {
-    {
+    let cancel = {
         let mut state = ctrl_c_state.lock().unwrap();
         state.command_in_progress = true;
         state.interrupt_command = false;
-    }
+        state.cancel_token = CancellationToken::new();
+        state.cancel_token.clone()
+    };
 
     print!("\x1b[2K\r\x1b[?25l"); // Clear current line and move up
     io::stdout().flush()?;
 
+    let result = run_command(command, output, &cancel, Some(&mut input)).await;
+    if !matches!(result, Ok(false)) {
+        reset_prompt(ctrl_c_state).await;
+    }
+
+    result
+}
+
+/// Runs one command given on the shell command line and returns the process exit code.
+/// The first Ctrl+C cancels the command at its next safe point, a second one exits immediately.
+pub async fn run_single_command(command: Commands, output: OutputFormat) -> i32 {
+    // completion scripts are generated without the configuration, e.g. from a shell rc file
+    if !matches!(command, Commands::Completions { .. }) {
+        if let Err(e) = CurseForgeConfig::try_get() {
+            output::print_error(output, &e.to_string(), false);
+            return 1;
+        }
+    }
+
+    let cancel = CancellationToken::new();
+    let signal_cancel = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            signal_cancel.cancel();
+            eprintln!("Stopping... press Ctrl+C again to force exit");
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
+
+    // tables are only paged when someone is at the terminal to turn the pages
+    let mut input = None;
+    let reads_terminal = output == OutputFormat::Table && !is_tui(&command);
+    if reads_terminal && io::stdin().is_terminal() && io::stdout().is_terminal() {
+        let (input_tx, input_rx) = tokio::sync::mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            for line in io::stdin().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if input_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        input = Some(input_rx);
+    }
+
+    match run_command(command, output, &cancel, input.as_mut()).await {
+        Ok(_) => 0,
+        Err(e) if e.is::<Interrupted>() => {
+            output::print_error(output, "Command was interrupted", true);
+            130
+        }
+        Err(e) => {
+            output::print_error(output, &e.to_string(), false);
+            1
+        }
+    }
+}
+
+/// Runs a parsed command. Returns false when the cli should exit. `input` carries lines typed
+/// while the command runs, tables are printed whole when there is none.
+async fn run_command(
+    command: Commands,
+    output: OutputFormat,
+    cancel: &CancellationToken,
+    input: Option<&mut UnboundedReceiver<String>>,
+) -> anyhow::Result<bool> {
+    // chatter is only printed for tables so json and csv output stays parseable
+    let table = output == OutputFormat::Table;
+
     match command {
         Commands::Init => {
             println!("Initializing WoW installation...");
             // Call your init function here
-            reset_prompt(ctrl_c_state).await;
-            Ok(true)
         }
-        Commands::View => {
-            println!("Viewing installed addons...");
-            installed_mods::get_installed_mods().await.unwrap();
-            reset_prompt(ctrl_c_state).await;
-            Ok(true)
+        Commands::View {
+            sort,
+            filter,
+            outdated,
+            unknown,
+        } => {
+            if table {
+                println!("Viewing installed addons...");
+            }
+            let options = ViewOptions {
+                sort,
+                filter,
+                outdated,
+                unknown,
+            };
+            let installed = installed_mods::view_installed_mods(&options, cancel)
+                .await
+                .map_err(cli_error)?;
+            completion::remember_installed(&installed);
+            if !print_structured(output, &installed)? {
+                let table = installed_mods::get_installed_mods(&installed).map_err(cli_error)?;
+                show_table(table, input, cancel).await;
+            }
         }
-        Commands::Search { name: filter } => {
-            if let Some(filter) = filter {
+        Commands::Search {
+            name: filter,
+            source,
+        } => {
+            let Some(filter) = filter else {
+                anyhow::bail!("Please provide either a text filter to search for");
+            };
+            if table {
                 println!("Searching for addon with filter: {}", filter);
-                let game_mods = curseforge_api::search_mods(1, &filter).await.unwrap();
-                println!("\nSearch Results ({} total):", game_mods.len());
-                for game_mod in &game_mods {
-                    println!(
-                        "  - {} (ID: {}). About: {}",
-                        game_mod.name, game_mod.id, game_mod.summary
-                    );
-                }
-
+            }
+            let provider = providers::provider(&source).map_err(cli_error)?;
+            let addons = cancel
+                .run(provider.search(&filter))
+                .await
+                .map_err(cli_error)?;
+            completion::remember_search_results(&addons);
+            let hits: Vec<SearchHit> = addons.iter().map(SearchHit::from).collect();
+            if !print_structured(output, &hits)? {
+                println!("\nSearch Results ({} total):", addons.len());
                 let mut table = mod_table::ModTable::new();
-                table.populate_mods_table(game_mods).unwrap();
-                table.print_table();
-            } else {
-                println!("Please provide either a text filter to search for");
-            }
-
-            reset_prompt(ctrl_c_state).await;
-            Ok(true)
-        }
-        Commands::Get { ids } => {
-            if let Some(ids) = ids {
-                println!("Getting addons with ids: {}", ids);
-                for id in ids.split(',') {
-                    if let Ok(id_num) = id.trim().parse::<u32>() {
-                        let game_mod = curseforge_api::get_mod_info(id_num).await.unwrap();
-                        // Get the right file for the game version
-                        let mod_file =
-                            game_version::get_mod_file_for_game_version(&game_mod).unwrap();
-                        // Download the file
-                        curseforge_api::get_mod_file(mod_file.id, &*mod_file.file_name)
-                            .await
-                            .unwrap();
-                    } else {
-                        println!("Invalid id: {}", id);
+                table.populate_mods_table(addons).map_err(cli_error)?;
+                show_table(table, input, cancel).await;
+            }
+        }
+        Commands::Info { id } => {
+            let game_mod = cancel
+                .run(curseforge_api::get_mod_info(id))
+                .await
+                .map_err(cli_error)?;
+            let description = cancel
+                .run(curseforge_api::get_mod_description(id))
+                .await
+                .map_err(cli_error)?;
+            let description = html::to_text(&description);
+            if !print_structured(output, &[ModDetails::new(&game_mod, &description)])? {
+                print_mod_card(&game_mod, &description);
+            }
+        }
+        Commands::Get {
+            ids,
+            source,
+            github,
+            file,
+            path,
+        } => {
+            let installed = match (file, path) {
+                (Some(file), _) => {
+                    vec![providers::local::install_zip(&file, cancel).map_err(cli_error)?]
+                }
+                (_, Some(path)) => {
+                    vec![providers::local::install_dir(&path, cancel).map_err(cli_error)?]
+                }
+                _ => {
+                    let (ids, source) = match github {
+                        Some(repos) => (Some(repos), providers::github::NAME.to_string()),
+                        None => (ids, source),
+                    };
+                    let Some(ids) = ids else {
+                        anyhow::bail!("Please provide addon ids to get.");
+                    };
+                    if table {
+                        println!("Getting addons with ids: {}", ids);
                     }
+                    get_mods(&ids, &source, cancel).await?
+                }
+            };
+            let results: Vec<InstallResult> = installed.iter().map(install_result).collect();
+            if !print_structured(output, &results)? {
+                for entry in installed {
+                    println!("Installed {} ({})", entry.name, entry.display_name);
                 }
-            } else {
-                println!("Please provide addon ids to get.");
             }
-
-            reset_prompt(ctrl_c_state).await;
-            Ok(true)
         }
         Commands::Delete { ids } => {
-            if let Some(ids) = ids {
+            let Some(ids) = ids else {
+                anyhow::bail!("Please provide addon ids to delete.");
+            };
+            if table {
                 println!("Deleting addons with ids: {}", ids);
-                // Call your delete by ids function here
-            } else {
-                println!("Please provide addon ids to delete.");
             }
-
-            reset_prompt(ctrl_c_state).await;
-            Ok(true)
+            let mut deleted = Vec::new();
+            for id in parse_ids(&ids)? {
+                cancel.check().map_err(cli_error)?;
+                deleted.push(curseforge_api::uninstall_mod(id).map_err(cli_error)?);
+            }
+            let results: Vec<InstallResult> = deleted.iter().map(install_result).collect();
+            if !print_structured(output, &results)? {
+                for entry in deleted {
+                    println!("Deleted {} ({})", entry.name, entry.directories.join(", "));
+                }
+            }
         }
         Commands::Update { ids, all, force } => {
-            if all {
-                println!("Updating all addons...");
-                // Call your update all function here
+            let ids = if all {
+                // settings are backed up first so a bad update can't take them with it
+                let name = format!(
+                    "pre-update-{}",
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                );
+                match wtf::backup_wtf(Some(name), &WtfFilter::default()) {
+                    Ok(backup) => eprintln!("Backed up SavedVariables to '{}'", backup.index.name),
+                    Err(e) => eprintln!("Skipping SavedVariables backup: {}", e),
+                }
+                None
             } else if let Some(ids) = ids {
-                println!("Updating addons with ids: {}", ids);
-                // Call your update by ids function here
+                Some(parse_ids(&ids)?)
             } else {
-                println!("Please provide either ids or use --all to update addons.");
+                anyhow::bail!("Please provide either ids or use --all to update addons.");
+            };
+
+            let plan = updater::plan_updates(ids.as_deref(), force, cancel)
+                .await
+                .map_err(cli_error)?;
+            let entries: Vec<&UpdatePlanEntry> =
+                plan.iter().map(|planned| &planned.entry).collect();
+            if !print_structured(output, &entries)? {
+                print_update_plan(&entries);
             }
 
-            reset_prompt(ctrl_c_state).await;
-            Ok(true)
+            let applied = updater::apply_updates(&plan, cancel)
+                .await
+                .map_err(cli_error)?;
+            if table {
+                println!("Updated {} addon(s)", applied.updated.len());
+            }
+            for (name, e) in &applied.failed {
+                eprintln!("Could not update {}: {}", name, e);
+            }
+            // failures are reported after the other addons are done, and fail the command so
+            // scripted updates notice them
+            let unchecked = entries
+                .iter()
+                .filter(|entry| !entry.error.is_empty())
+                .count();
+            if unchecked > 0 || !applied.failed.is_empty() {
+                anyhow::bail!(
+                    "{} addon(s) could not be checked and {} could not be updated",
+                    unchecked,
+                    applied.failed.len()
+                );
+            }
+        }
+        Commands::Rollback { id, to, list } => {
+            if list {
+                list_backups(id, output)?;
+            } else {
+                let entry = backup::rollback(id, to, cancel).map_err(cli_error)?;
+                if !print_structured(output, &[install_result(&entry)])? {
+                    println!(
+                        "Rolled back {} to file {} {}",
+                        entry.name, entry.file_id, entry.display_name
+                    );
+                }
+            }
+        }
+        Commands::Link { path } => {
+            let entry = providers::dev::link(&path).map_err(cli_error)?;
+            if !print_structured(output, &[install_result(&entry)])? {
+                println!(
+                    "Linked {} ({}) from {}",
+                    entry.name, entry.display_name, entry.file_name
+                );
+            }
+        }
+        Commands::Unlink { target } => {
+            let unlinked = providers::dev::unlink(&target, cancel).map_err(cli_error)?;
+            let results: Vec<InstallResult> = std::iter::once(&unlinked.link)
+                .chain(&unlinked.restored)
+                .map(install_result)
+                .collect();
+            if !print_structured(output, &results)? {
+                println!(
+                    "Unlinked {} from {}",
+                    unlinked.link.name, unlinked.link.file_name
+                );
+                for entry in &unlinked.restored {
+                    println!("Restored {} ({})", entry.name, entry.display_name);
+                }
+            }
+        }
+        Commands::Export { file, pin } => {
+            let pinned = match pin {
+                Some(pin) => parse_ids(&pin)?,
+                None => Vec::new(),
+            };
+            let profile = profile::export_profile(&pinned).map_err(cli_error)?;
+            let json = serde_json::to_string_pretty(&profile)?;
+            match file {
+                Some(file) => {
+                    std::fs::write(&file, json + "\n")?;
+                    eprintln!(
+                        "Exported {} addon(s) to {}",
+                        profile.addons.len(),
+                        file.display()
+                    );
+                }
+                None => println!("{}", json),
+            }
+        }
+        Commands::Import {
+            file,
+            from: Some(manager),
+            ..
+        } => {
+            let addons =
+                migrate::read_state(manager, &file.to_string_lossy()).map_err(cli_error)?;
+            if table {
+                println!(
+                    "Taking over {} addon(s) from {}",
+                    addons.len(),
+                    manager.label()
+                );
+            }
+            let adopted = migrate::adopt(manager, &addons, cancel)
+                .await
+                .map_err(cli_error)?;
+            let results: Vec<InstallResult> = adopted.iter().map(install_result).collect();
+            if !print_structured(output, &results)? {
+                for entry in adopted {
+                    println!(
+                        "Recorded {} ({}) in {}",
+                        entry.name,
+                        entry.display_name,
+                        entry.directories.join(", ")
+                    );
+                }
+            }
+        }
+        Commands::Import { file, exact, .. } => {
+            let profile = profile::read_profile(&file).map_err(cli_error)?;
+            let mode = if exact {
+                ImportMode::Exact
+            } else {
+                ImportMode::Latest
+            };
+            if table {
+                println!(
+                    "Importing {} addon(s) from {}",
+                    profile.addons.len(),
+                    file.display()
+                );
+            }
+            let installed = profile::import_profile(&profile, mode, cancel)
+                .await
+                .map_err(cli_error)?;
+            let results: Vec<InstallResult> = installed.iter().map(install_result).collect();
+            if !print_structured(output, &results)? {
+                for entry in installed {
+                    println!("Installed {} ({})", entry.name, entry.display_name);
+                }
+            }
+        }
+        Commands::Snapshot { action } => run_snapshot_command(action, output, cancel)?,
+        Commands::Wtf { action } => run_wtf_command(action, output, cancel)?,
+        Commands::Group { action } => run_group_command(action, output)?,
+        Commands::Character { action } => run_character_command(action, output)?,
+        Commands::Cache { action } => run_cache_command(action, output)?,
+        #[cfg(feature = "tui")]
+        Commands::Tui => {
+            // the interactive cli is already reading the terminal
+            if input.is_some() {
+                anyhow::bail!(
+                    "The TUI can't run inside the interactive cli, start it with `addown tui`"
+                );
+            }
+            crate::tui::run(cancel).await.map_err(cli_error)?;
+        }
+        Commands::Completions { shell } => {
+            // generate panics on write errors, so the script is written here where a closed
+            // pipe such as `| head` can be handled
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Addown::command(), "addown", &mut script);
+            match io::stdout().write_all(&script) {
+                Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+                _ => {}
+            }
         }
         Commands::Exit => {
             println!("Exiting...");
-            Ok(false)
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Converts a library error for the cli, keeping `Interrupted` so cancelled commands can be
+/// told apart from failed ones
+fn cli_error(e: Box<dyn std::error::Error>) -> anyhow::Error {
+    match e.downcast::<Interrupted>() {
+        Ok(interrupted) => anyhow::Error::new(*interrupted),
+        Err(e) => anyhow::anyhow!("{}", e),
+    }
+}
+
+/// Prints json or csv results, converting the library's boxed errors for the cli
+fn print_structured<T: serde::Serialize>(output: OutputFormat, rows: &[T]) -> anyhow::Result<bool> {
+    output::print_structured(output, rows).map_err(cli_error)
+}
+
+fn install_result(entry: &ManifestEntry) -> InstallResult {
+    InstallResult {
+        id: entry.mod_id,
+        name: entry.name.clone(),
+        file_id: entry.file_id,
+        version: entry.display_name.clone(),
+        folders: entry.directories.join(";"),
+    }
+}
+
+/// Whether the command takes over the whole terminal
+#[cfg_attr(not(feature = "tui"), allow(unused_variables))]
+fn is_tui(command: &Commands) -> bool {
+    #[cfg(feature = "tui")]
+    if matches!(command, Commands::Tui) {
+        return true;
+    }
+    false
+}
+
+/// Pages through a table when there is input to read page commands from, otherwise prints it whole
+async fn show_table(
+    mut table: mod_table::ModTable,
+    input: Option<&mut UnboundedReceiver<String>>,
+    cancel: &CancellationToken,
+) {
+    match input {
+        Some(input) => table.page_table(input, cancel).await,
+        None => table.print_table(),
+    }
+}
+
+/// Parses a comma separated list of mod ids
+fn parse_ids(ids: &str) -> anyhow::Result<Vec<u32>> {
+    ids.split(',')
+        .map(|id| {
+            id.trim()
+                .parse::<u32>()
+                .map_err(|_| anyhow::anyhow!("Invalid id: {}", id))
+        })
+        .collect()
+}
+
+fn print_update_plan(entries: &[&UpdatePlanEntry]) {
+    if entries.is_empty() {
+        println!("No addons installed by addown to update");
+    }
+    for entry in entries {
+        if !entry.error.is_empty() {
+            println!(
+                "  - {} (ID: {}): could not be checked, {}",
+                entry.name, entry.id, entry.error
+            );
+        } else if entry.needs_update {
+            println!(
+                "  - {} (ID: {}): {} -> {}",
+                entry.name, entry.id, entry.installed_version, entry.target_version
+            );
+            if !entry.local_version.is_empty()
+                && !entry.installed_version.contains(&entry.local_version)
+            {
+                println!("    installed files report version {}", entry.local_version);
+            }
+            for line in entry.changelog.lines() {
+                println!("    {}", line);
+            }
+        } else {
+            println!(
+                "  - {} (ID: {}): up to date ({})",
+                entry.name, entry.id, entry.installed_version
+            );
+        }
+    }
+}
+
+/// Prints everything CurseForge knows about a mod as a detail card
+fn print_mod_card(game_mod: &Mod, description: &str) {
+    let width = mod_table::terminal_width().clamp(40, 120);
+    let rule = "─".repeat(width);
+    let field = |label: &str, value: &str| {
+        if !value.is_empty() {
+            println!("{:<12} {}", label, value);
+        }
+    };
+
+    println!("{}", rule);
+    println!("{} (ID: {})", game_mod.name, game_mod.id);
+    for line in mod_table::wrap(&game_mod.summary, width) {
+        println!("{}", line);
+    }
+    println!("{}", rule);
+
+    let authors: Vec<&str> = game_mod.authors.iter().map(|a| a.name.as_str()).collect();
+    let categories: Vec<&str> = game_mod
+        .categories
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    field("Authors", &authors.join(", "));
+    field("Categories", &categories.join(", "));
+    field(
+        "Downloads",
+        &format!(
+            "{} ({} thumbs up)",
+            game_mod.download_count, game_mod.thumbs_up_count
+        ),
+    );
+    field("Released", &format_api_date(&game_mod.date_released));
+    field("Updated", &format_api_date(&game_mod.date_modified));
+    let links = &game_mod.links;
+    field("Website", links.website_url.as_deref().unwrap_or_default());
+    field("Source", links.source_url.as_deref().unwrap_or_default());
+    field("Issues", links.issues_url.as_deref().unwrap_or_default());
+    field("Wiki", links.wiki_url.as_deref().unwrap_or_default());
+
+    if !description.is_empty() {
+        println!("\nDescription");
+        for paragraph in description.lines() {
+            for line in mod_table::wrap(paragraph, width.saturating_sub(2)) {
+                println!("  {}", line);
+            }
+        }
+    }
+
+    if !game_mod.screenshots.is_empty() {
+        println!("\nScreenshots");
+        for screenshot in &game_mod.screenshots {
+            println!("  - {}: {}", screenshot.title, screenshot.url);
+        }
+    }
+
+    let latest_files = game_version::latest_files_by_game_version(game_mod);
+    if !latest_files.is_empty() {
+        println!("\nLatest files (* is the installed game version)");
+        for file in latest_files {
+            let marker = if game_version::is_installed_game_version(&file.game_version) {
+                "*"
+            } else {
+                " "
+            };
+            println!(
+                "  {}{:<10} {:<8} {:<10} {}",
+                marker,
+                file.game_version,
+                game_version::release_type_name(file.release_type),
+                file.file_id,
+                file.filename
+            );
+        }
+    }
+}
+
+/// Downloads and installs each addon in a comma separated id list from a provider, stopping
+/// between addons on Ctrl+C
+async fn get_mods(
+    ids: &str,
+    source: &str,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Vec<ManifestEntry>> {
+    let provider = providers::provider(source).map_err(cli_error)?;
+    let mut installed = Vec::new();
+    for id in ids.split(',').map(str::trim) {
+        cancel.check().map_err(cli_error)?;
+        if id.is_empty() || provider.local_id(id).is_err() {
+            eprintln!("Invalid id: {}", id);
+            continue;
+        }
+        let entry = providers::install(provider.as_ref(), id, cancel)
+            .await
+            .map_err(cli_error)?;
+        installed.push(entry);
+    }
+
+    Ok(installed)
+}
+
+fn list_backups(mod_id: u32, output: OutputFormat) -> anyhow::Result<()> {
+    let backups = backup::list_backups(mod_id).map_err(cli_error)?;
+    let rows: Vec<ArchiveRow> = backups
+        .iter()
+        .map(|backup| ArchiveRow {
+            name: backup.path.display().to_string(),
+            file_id: Some(backup.file_id),
+            detail: backup
+                .entry
+                .as_ref()
+                .map(|entry| entry.display_name.clone())
+                .unwrap_or_else(|| "untracked version".to_string()),
+            date: format_timestamp(backup.created_at),
+            size_bytes: None,
+        })
+        .collect();
+    if print_structured(output, &rows)? {
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("No backups found for mod {}", mod_id);
+    }
+    for row in rows {
+        println!(
+            "  - File ID: {} ({}), backed up {}",
+            row.file_id.unwrap_or_default(),
+            row.detail,
+            row.date
+        );
+    }
+
+    Ok(())
+}
+
+fn run_snapshot_command(
+    action: SnapshotCommands,
+    output: OutputFormat,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    match action {
+        SnapshotCommands::Create { name } => {
+            let snapshot = snapshot::create_snapshot(name).map_err(cli_error)?;
+            let row = snapshot_row(&snapshot);
+            if !print_structured(output, &[row])? {
+                println!(
+                    "Created snapshot '{}' with {} folder(s) ({})",
+                    snapshot.index.name,
+                    snapshot.index.folders.len(),
+                    download_cache::format_size(snapshot.size)
+                );
+            }
+        }
+        SnapshotCommands::List => {
+            let snapshots = snapshot::list_snapshots().map_err(cli_error)?;
+            let rows: Vec<ArchiveRow> = snapshots.iter().map(snapshot_row).collect();
+            if print_structured(output, &rows)? {
+                return Ok(());
+            }
+            if snapshots.is_empty() {
+                println!("No snapshots found");
+            }
+            for snapshot in snapshots {
+                println!(
+                    "  - {} ({}), {} folder(s), {}",
+                    snapshot.index.name,
+                    format_timestamp(snapshot.index.created_at),
+                    snapshot.index.folders.len(),
+                    download_cache::format_size(snapshot.size)
+                );
+            }
+        }
+        SnapshotCommands::Restore { name } => {
+            snapshot::restore_snapshot(&name, cancel).map_err(cli_error)?;
+            if output == OutputFormat::Table {
+                println!("Restored snapshot '{}'", name);
+            }
+        }
+        SnapshotCommands::Diff { a, b } => {
+            let changes = snapshot::diff_snapshots(&a, &b).map_err(cli_error)?;
+            let rows: Vec<SnapshotDiffRow> = changes
+                .into_iter()
+                .map(|change| match change {
+                    SnapshotChange::Added { name, version } => SnapshotDiffRow {
+                        change: "added".to_string(),
+                        name,
+                        from: String::new(),
+                        to: version,
+                    },
+                    SnapshotChange::Removed { name, version } => SnapshotDiffRow {
+                        change: "removed".to_string(),
+                        name,
+                        from: version,
+                        to: String::new(),
+                    },
+                    SnapshotChange::Changed { name, from, to } => SnapshotDiffRow {
+                        change: "changed".to_string(),
+                        name,
+                        from,
+                        to,
+                    },
+                })
+                .collect();
+            if print_structured(output, &rows)? {
+                return Ok(());
+            }
+            if rows.is_empty() {
+                println!("No differences between '{}' and '{}'", a, b);
+            }
+            for row in rows {
+                match row.change.as_str() {
+                    "added" => println!("  + {} {}", row.name, row.to),
+                    "removed" => println!("  - {} {}", row.name, row.from),
+                    _ => println!("  ~ {} {} -> {}", row.name, row.from, row.to),
+                }
+            }
         }
     }
+
+    Ok(())
+}
+
+fn snapshot_row(snapshot: &snapshot::Snapshot) -> ArchiveRow {
+    ArchiveRow {
+        name: snapshot.index.name.clone(),
+        file_id: None,
+        detail: format!("{} folders", snapshot.index.folders.len()),
+        date: format_timestamp(snapshot.index.created_at),
+        size_bytes: Some(snapshot.size),
+    }
+}
+
+fn run_wtf_command(
+    action: WtfCommands,
+    output: OutputFormat,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    match action {
+        WtfCommands::Backup {
+            name,
+            account,
+            character,
+        } => {
+            let filter = WtfFilter { account, character };
+            let backup = wtf::backup_wtf(name, &filter).map_err(cli_error)?;
+            if !print_structured(output, &[wtf_row(&backup)])? {
+                println!(
+                    "Created WTF backup '{}' with {} folder(s) ({})",
+                    backup.index.name,
+                    backup.index.units.len(),
+                    download_cache::format_size(backup.size)
+                );
+            }
+        }
+        WtfCommands::List => {
+            let backups = wtf::list_wtf_backups().map_err(cli_error)?;
+            let rows: Vec<ArchiveRow> = backups.iter().map(wtf_row).collect();
+            if print_structured(output, &rows)? {
+                return Ok(());
+            }
+            if backups.is_empty() {
+                println!("No WTF backups found");
+            }
+            for backup in backups {
+                println!(
+                    "  - {} ({}), {} folder(s), {}",
+                    backup.index.name,
+                    format_timestamp(backup.index.created_at),
+                    backup.index.units.len(),
+                    download_cache::format_size(backup.size)
+                );
+            }
+        }
+        WtfCommands::Restore {
+            name,
+            account,
+            character,
+        } => {
+            let filter = WtfFilter { account, character };
+            let restored = wtf::restore_wtf(&name, &filter, cancel).map_err(cli_error)?;
+            if !print_structured(output, &restored)? {
+                for unit in restored {
+                    println!("  - Restored {}", unit);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_group_command(action: GroupCommands, output: OutputFormat) -> anyhow::Result<()> {
+    match action {
+        GroupCommands::List => {
+            let groups = groups::Groups::load().map_err(cli_error)?;
+            let manifest = Manifest::load().map_err(cli_error)?;
+            let rows: Vec<GroupRow> = groups
+                .groups
+                .iter()
+                .map(|(name, group)| group_row(name, group, &manifest))
+                .collect();
+            if print_structured(output, &rows)? {
+                return Ok(());
+            }
+            if rows.is_empty() {
+                println!("No addon groups, create one with `group add <name> -i <ids>`");
+            }
+            for row in rows {
+                println!(
+                    "  - {}{}: {}",
+                    row.name,
+                    if row.disabled { " (disabled)" } else { "" },
+                    row.addons.replace(';', ", ")
+                );
+            }
+        }
+        GroupCommands::Add { name, ids } => {
+            let group = groups::add_to_group(&name, &parse_ids(&ids)?).map_err(cli_error)?;
+            let manifest = Manifest::load().map_err(cli_error)?;
+            if !print_structured(output, &[group_row(&name, &group, &manifest)])? {
+                println!("Group '{}' has {} addon(s)", name, group.addons.len());
+            }
+        }
+        GroupCommands::Remove { name, ids } => {
+            let ids = ids.map(|ids| parse_ids(&ids)).transpose()?;
+            let remaining = groups::remove_from_group(&name, ids.as_deref()).map_err(cli_error)?;
+            let manifest = Manifest::load().map_err(cli_error)?;
+            let rows: Vec<GroupRow> = remaining
+                .iter()
+                .map(|group| group_row(&name, group, &manifest))
+                .collect();
+            if !print_structured(output, &rows)? {
+                match remaining {
+                    Some(group) => println!("Group '{}' has {} addon(s)", name, group.addons.len()),
+                    None => println!("Removed group '{}'", name),
+                }
+            }
+        }
+        GroupCommands::Enable {
+            name,
+            account,
+            character,
+        } => set_group_enabled(&name, true, account, character, output)?,
+        GroupCommands::Disable {
+            name,
+            account,
+            character,
+        } => set_group_enabled(&name, false, account, character, output)?,
+    }
+
+    Ok(())
+}
+
+fn group_row(name: &str, group: &groups::Group, manifest: &Manifest) -> GroupRow {
+    GroupRow {
+        name: name.to_string(),
+        disabled: group.disabled,
+        ids: group
+            .addons
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(";"),
+        addons: group
+            .addons
+            .iter()
+            .map(|id| {
+                manifest
+                    .find(*id)
+                    .map(|entry| entry.name.clone())
+                    .unwrap_or_else(|| id.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join(";"),
+    }
+}
+
+/// Enables or disables a group, in the AddOns.txt of characters when an account or
+/// character is given and by moving its folders otherwise
+fn set_group_enabled(
+    name: &str,
+    enabled: bool,
+    account: Option<String>,
+    character: Option<String>,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
+    let filter =
+        (account.is_some() || character.is_some()).then_some(WtfFilter { account, character });
+    let folders = groups::set_group_enabled(name, enabled, filter.as_ref()).map_err(cli_error)?;
+    if !print_structured(output, &folders)? {
+        let state = if enabled { "Enabled" } else { "Disabled" };
+        match filter {
+            Some(_) => println!(
+                "{} {} folder(s) of '{}' in AddOns.txt",
+                state,
+                folders.len(),
+                name
+            ),
+            None => println!("{} '{}', moved {} folder(s)", state, name, folders.len()),
+        }
+    }
+    Ok(())
+}
+
+fn run_character_command(action: CharacterCommands, output: OutputFormat) -> anyhow::Result<()> {
+    let (folders, account, character, enabled) = match action {
+        CharacterCommands::List { account, character } => {
+            let characters = addons_txt::list_characters(&WtfFilter { account, character })
+                .map_err(cli_error)?;
+            let rows: Vec<CharacterAddonRow> = characters
+                .iter()
+                .flat_map(|found| {
+                    found
+                        .addons
+                        .iter()
+                        .map(|(folder, enabled)| CharacterAddonRow {
+                            account: found.account.clone(),
+                            realm: found.realm.clone(),
+                            character: found.character.clone(),
+                            folder: folder.clone(),
+                            enabled: *enabled,
+                        })
+                })
+                .collect();
+            if print_structured(output, &rows)? {
+                return Ok(());
+            }
+            if characters.is_empty() {
+                println!("No characters found in WTF");
+            }
+            for found in characters {
+                let disabled: Vec<&str> = found
+                    .addons
+                    .iter()
+                    .filter(|(_, enabled)| !enabled)
+                    .map(|(folder, _)| folder.as_str())
+                    .collect();
+                println!(
+                    "  - {}/{} ({}): {} of {} addons enabled",
+                    found.realm,
+                    found.character,
+                    found.account,
+                    found.addons.len() - disabled.len(),
+                    found.addons.len()
+                );
+                if !disabled.is_empty() {
+                    println!("    disabled: {}", disabled.join(", "));
+                }
+            }
+            return Ok(());
+        }
+        CharacterCommands::Enable {
+            folders,
+            account,
+            character,
+            ..
+        } => (folders, account, character, true),
+        CharacterCommands::Disable {
+            folders,
+            account,
+            character,
+            ..
+        } => (folders, account, character, false),
+    };
+
+    let folders: Vec<String> = folders.split(',').map(str::to_string).collect();
+    let folders = addons_txt::installed_names(&folders).map_err(cli_error)?;
+    let written = addons_txt::set_enabled(&WtfFilter { account, character }, &folders, enabled)
+        .map_err(cli_error)?;
+    let written: Vec<String> = written
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    if !print_structured(output, &written)? {
+        println!(
+            "{} {} for {} character(s)",
+            if enabled { "Enabled" } else { "Disabled" },
+            folders.join(", "),
+            written.len()
+        );
+    }
+    Ok(())
+}
+
+fn wtf_row(backup: &wtf::WtfBackup) -> ArchiveRow {
+    ArchiveRow {
+        name: backup.index.name.clone(),
+        file_id: None,
+        detail: format!("{} folders", backup.index.units.len()),
+        date: format_timestamp(backup.index.created_at),
+        size_bytes: Some(backup.size),
+    }
+}
+
+fn run_cache_command(action: CacheCommands, output: OutputFormat) -> anyhow::Result<()> {
+    match action {
+        CacheCommands::List => {
+            let entries = download_cache::list_entries().map_err(cli_error)?;
+            let rows: Vec<ArchiveRow> = entries
+                .iter()
+                .map(|entry| ArchiveRow {
+                    name: entry.file_name.clone(),
+                    file_id: Some(entry.file_id),
+                    detail: entry.hash.clone(),
+                    date: entry
+                        .last_used
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|since| format_timestamp(since.as_secs()))
+                        .unwrap_or_default(),
+                    size_bytes: Some(entry.size),
+                })
+                .collect();
+            if print_structured(output, &rows)? {
+                return Ok(());
+            }
+            if entries.is_empty() {
+                println!("The download cache is empty");
+            }
+            for entry in &entries {
+                let age = entry.last_used.elapsed().unwrap_or_default().as_secs() / 86400;
+                // entries cached before providers were recorded have none
+                let provider = if entry.provider.is_empty() {
+                    "unknown"
+                } else {
+                    &entry.provider
+                };
+                println!(
+                    "  - {} (Provider: {}, File ID: {}, sha1: {}) {}, last used {} day(s) ago",
+                    entry.file_name,
+                    provider,
+                    entry.file_id,
+                    entry.hash,
+                    download_cache::format_size(entry.size),
+                    age
+                );
+            }
+        }
+        CacheCommands::Prune { older_than } => {
+            let max_age = download_cache::parse_age(&older_than).map_err(cli_error)?;
+            let (removed, freed) = download_cache::prune(max_age).map_err(cli_error)?;
+            if !print_structured(
+                output,
+                &[CacheSummary {
+                    files: removed,
+                    size_bytes: freed,
+                }],
+            )? {
+                println!(
+                    "Removed {} cached file(s), freed {}",
+                    removed,
+                    download_cache::format_size(freed)
+                );
+            }
+        }
+        CacheCommands::Size => {
+            let entries = download_cache::list_entries().map_err(cli_error)?;
+            let size = entries.iter().map(|entry| entry.size).sum();
+            if !print_structured(
+                output,
+                &[CacheSummary {
+                    files: entries.len(),
+                    size_bytes: size,
+                }],
+            )? {
+                println!(
+                    "{} cached file(s) using {}",
+                    entries.len(),
+                    download_cache::format_size(size)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Totals reported by `cache size` and `cache prune`
+#[derive(serde::Serialize)]
+struct CacheSummary {
+    files: usize,
+    size_bytes: u64,
 }
 
 //async fn reset_prompt(progress_task: JoinHandle<()>, ctrl_c_state: &Arc<Mutex<CtrlCState>>) {
@@ -354,6 +1632,29 @@ async fn reset_prompt(ctrl_c_state: &Arc<Mutex<CtrlCState>>) {
     }
 }
 
+/// Replaces the editor's history with the saved one, which has no repeated lines
+fn load_history(rl: &mut Editor<AddownHelper, DefaultHistory>, history: &CommandHistory) {
+    rl.clear_history().ok();
+    for line in history.lines() {
+        rl.add_history_entry(line.as_str()).ok();
+    }
+}
+
+/// Saves a line to the history file and appends it to the editor's history. The editor keeps
+/// repeats until the next start, which loads the file without them
+fn save_history(
+    rl: &mut Editor<AddownHelper, DefaultHistory>,
+    history: Option<&mut CommandHistory>,
+    line: &str,
+) {
+    if let Some(history) = history {
+        if let Err(e) = history.add(line) {
+            eprintln!("Could not save history: {}", e);
+        }
+    }
+    rl.add_history_entry(line).ok();
+}
+
 /// Handles commands like `get -n "Details! Damage Meter"`
 fn parse_quoted_args(input: &str) -> Vec<String> {
     let mut args = Vec::new();
@@ -411,8 +1712,23 @@ pub fn crate_rustyline_background_loop(
     rusty_ctrl_c_state_clone: Arc<Mutex<CtrlCState>>,
 ) {
     std::thread::spawn(move || {
-        let mut rl = DefaultEditor::new().unwrap();
-        //load_history(&mut rl);
+        let config = Config::builder()
+            .completion_type(CompletionType::List)
+            .max_history_size(CurseForgeConfig::get().history_size)
+            .unwrap()
+            .build();
+        let mut rl: Editor<AddownHelper, DefaultHistory> = Editor::with_config(config).unwrap();
+        rl.set_helper(Some(AddownHelper::new()));
+        let mut history = match CommandHistory::load() {
+            Ok(history) => Some(history),
+            Err(e) => {
+                eprintln!("History is not saved this session: {}", e);
+                None
+            }
+        };
+        if let Some(history) = &history {
+            load_history(&mut rl, history);
+        }
 
         loop {
             let state = rusty_ctrl_c_state_clone.lock().unwrap();
@@ -430,7 +1746,6 @@ pub fn crate_rustyline_background_loop(
                 };
             drop(state);
 
-            //save_history(&mut rl);
             match rl.readline(prompt) {
                 Ok(line) => {
                     let mut state = rusty_ctrl_c_state_clone.lock().unwrap();
@@ -443,7 +1758,10 @@ pub fn crate_rustyline_background_loop(
                         continue;
                     }
 
-                    rl.add_history_entry(line.as_str()).unwrap();
+                    // page commands and other input for a running command aren't history
+                    if !state.command_in_progress {
+                        save_history(&mut rl, history.as_mut(), &line);
+                    }
                     if input_tx_clone.send(InputEvent::Command(line)).is_err() {
                         break; // Main task has stopped
                     }
@@ -517,14 +1835,64 @@ pub fn create_ctrlc_background_loop(
 }
 
 fn show_help() {
-    println!("Available commands:");
+    println!("Available commands (also usable from the shell, e.g. `addown update --all`):");
     println!("  init                   Initialize WoW installation");
     println!("  view                   View installed addons");
+    println!("  view -s name|version|updated|id|size  Sort installed addons");
+    println!("  view -f <text>         Only show addons matching the text");
+    println!("  view --outdated        Only show addons with a newer version");
+    println!("  view --unknown         Only show addons not matched to a CurseForge project");
     println!("  search -f <filter>     Search for addon by filter");
+    println!("  search --source <src>  Search curseforge (default), wowi, wago or github");
+    println!("  info -i <id>           Show the description, links and files of an addon");
     println!("  get -i <ids>           Get addons with ids (comma-separated)");
+    println!("  get --source <src>     Get addons from curseforge (default), wowi, wago or github");
+    println!("  get --github <owner/repo>  Get the latest release of an addon published on GitHub");
+    println!("  get --file <zip>       Install an addon zip from disk, update leaves it alone");
+    println!("  get --path <dir>       Install an addon folder from disk, update leaves it alone");
     println!("  delete -i <ids>        Delete addons with ids (comma-separated)");
     println!("  update -i <ids> [-f]   Update addons with ids (comma-separated). Force to reinstall even if no update is needed");
     println!("  update -a [-f]         Update all addons. For to reinstall all addons even those that don't need updates.");
+    println!("  rollback -i <id> [--to <fileId>]  Restore the previous (or a specific) version of an addon");
+    println!("  rollback -i <id> -l    List the backups kept for an addon");
+    println!("  export [file] [--pin <ids>]  Write the installed addons as a profile");
+    println!("  import <file> [--exact|--latest]  Install the addons of a profile, the exact files or the newest");
+    println!("  import <file> --from <curseforge|wowup|ajour>  Take over the addons another manager installed");
+    println!("  group list             List addon groups");
+    println!("  group add <name> -i <ids>  Add installed addons to a group");
+    println!("  group remove <name> [-i <ids>]  Remove addons from a group, or the whole group");
+    println!("  group enable|disable <name> [-c <character>] [-a <account>]  Move a group's folders in or out of AddOns, or set them in characters' AddOns.txt");
+    println!("  character list [-c <character>] [-a <account>]  List the addons each character has enabled");
+    println!("  character enable|disable <folders> (-c <character>|--all) [-a <account>]  Change which addons characters load");
+    println!("  link <path>            Symlink an addon working copy into AddOns for development");
+    println!(
+        "  unlink <path|name|id>  Remove a linked working copy and restore the release it replaced"
+    );
+    println!("  snapshot create [name] Archive the whole AddOns folder and the manifest");
+    println!("  snapshot list          List saved snapshots");
+    println!("  snapshot restore <name>  Replace the AddOns folder with a snapshot, saving the current one first");
+    println!("  snapshot diff <a> <b>  Show which addons changed between two snapshots");
+    println!("  wtf backup [name] [-a <account>] [-c <character>]  Back up SavedVariables");
+    println!("  wtf list               List WTF backups");
+    println!("  wtf restore <name> [-a <account>] [-c <character>]  Restore SavedVariables (close WoW first)");
+    println!("  cache list             List cached addon archives");
+    println!(
+        "  cache prune --older-than <age>  Remove cached archives unused for <age> (e.g. 30d)"
+    );
+    println!("  cache size             Show the total size of the download cache");
+    println!("  <command> -o json|csv  Print results as json or csv instead of a table");
     println!("  help                   Show this help message");
+    #[cfg(feature = "tui")]
+    println!(
+        "  tui                    Open the full screen interface (from the shell: addown tui)"
+    );
+    println!("  completions <shell>    Print a bash, zsh or fish completion script");
     println!("  exit, quit             Exit the CLI");
+    println!();
+    println!("Long view and search tables are shown a page at a time:");
+    println!("  n, p, f, l             Next, previous, first or last page");
+    println!("  <number>               Jump to a page");
+    println!("  q                      Stop paging");
+    println!();
+    println!("Tab completes commands, flags and addons, Ctrl+R searches the history.");
 }
@@ -0,0 +1,307 @@
+use crate::cli_loop::Addown;
+use crate::installed_mods::InstalledAddon;
+use crate::manifest::Manifest;
+use crate::providers::AddonInfo;
+use clap::{Arg, Command, CommandFactory};
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::{Hinter, HistoryHinter};
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::sync::Mutex;
+
+/// Commands of the interactive cli that clap doesn't define
+const REPL_COMMANDS: [&str; 3] = ["help", "exit", "quit"];
+/// Most search results remembered for completion
+const MAX_SEARCH_RESULTS: usize = 50;
+
+/// Addons seen by `view` and `search`, offered when completing ids and names
+static KNOWN_ADDONS: Mutex<KnownAddons> = Mutex::new(KnownAddons {
+    installed: Vec::new(),
+    searched: Vec::new(),
+});
+
+struct KnownAddons {
+    installed: Vec<(String, String)>,
+    searched: Vec<(String, String)>,
+}
+
+/// Remembers the addons found by a scan of AddOns
+pub fn remember_installed(addons: &[InstalledAddon]) {
+    let mut known = KNOWN_ADDONS.lock().unwrap();
+    known.installed = addons
+        .iter()
+        .filter(|addon| addon.id != 0)
+        .map(|addon| (addon.id.to_string(), addon.name.clone()))
+        .collect();
+}
+
+/// Remembers search results, most recent search first
+pub fn remember_search_results(addons: &[AddonInfo]) {
+    let mut known = KNOWN_ADDONS.lock().unwrap();
+    let mut searched: Vec<(String, String)> = addons
+        .iter()
+        .map(|addon| (addon.id.clone(), addon.name.clone()))
+        .collect();
+    for previous in known.searched.drain(..) {
+        if !searched.iter().any(|(id, _)| *id == previous.0) {
+            searched.push(previous);
+        }
+    }
+    searched.truncate(MAX_SEARCH_RESULTS);
+    known.searched = searched;
+}
+
+/// Addons in the manifest plus any others the last `view` found
+fn installed_addons() -> Vec<(String, String)> {
+    let mut addons: Vec<(String, String)> = Manifest::load()
+        .map(|manifest| {
+            manifest
+                .addons
+                .into_iter()
+                .map(|entry| (entry.mod_id.to_string(), entry.name))
+                .collect()
+        })
+        .unwrap_or_default();
+    for addon in &KNOWN_ADDONS.lock().unwrap().installed {
+        if !addons.iter().any(|(id, _)| *id == addon.0) {
+            addons.push(addon.clone());
+        }
+    }
+    addons.sort_by_key(|(_, name)| name.to_lowercase());
+    addons
+}
+
+fn searched_addons() -> Vec<(String, String)> {
+    KNOWN_ADDONS.lock().unwrap().searched.clone()
+}
+
+/// Completes and hints commands, flags and addons in the interactive cli
+pub struct AddownHelper {
+    command: Command,
+    history_hinter: HistoryHinter,
+}
+
+impl Default for AddownHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AddownHelper {
+    pub fn new() -> Self {
+        let mut command = Addown::command();
+        // propagates global flags like --output to every subcommand
+        command.build();
+        Self {
+            command,
+            history_hinter: HistoryHinter::new(),
+        }
+    }
+
+    /// Candidates for `current`, the word under the cursor, after the words typed before it.
+    /// Returns how many bytes of `current` the candidates replace from its start.
+    fn candidates(&self, words: &[&str], current: &str) -> (usize, Vec<Pair>) {
+        let mut command = &self.command;
+        let mut path: Vec<&str> = Vec::new();
+        for word in words {
+            if let Some(subcommand) = command.find_subcommand(word) {
+                command = subcommand;
+                path.push(subcommand.get_name());
+            }
+        }
+
+        if let Some(arg) = words.last().and_then(|word| find_flag(command, word)) {
+            if arg.get_action().takes_values() {
+                return value_candidates(path.first().copied(), arg, current);
+            }
+        }
+
+        let mut candidates = Vec::new();
+        if !current.starts_with('-') {
+            for subcommand in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+                let about = subcommand
+                    .get_about()
+                    .map(|about| about.to_string())
+                    .unwrap_or_default();
+                candidates.push(described(subcommand.get_name(), &about));
+            }
+            if path.is_empty() {
+                for name in REPL_COMMANDS {
+                    if !candidates.iter().any(|pair| pair.replacement == name) {
+                        candidates.push(described(name, ""));
+                    }
+                }
+            }
+        }
+        if !path.is_empty() {
+            for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+                let help = arg
+                    .get_help()
+                    .map(|help| help.to_string())
+                    .unwrap_or_default();
+                if let Some(long) = arg.get_long() {
+                    candidates.push(described(&format!("--{}", long), &help));
+                }
+                if let Some(short) = arg.get_short() {
+                    candidates.push(described(&format!("-{}", short), &help));
+                }
+            }
+        }
+
+        candidates.retain(|pair| pair.replacement.starts_with(current));
+        (0, candidates)
+    }
+}
+
+/// Finds the flag a word like `-i` or `--ids` refers to
+fn find_flag<'a>(command: &'a Command, word: &str) -> Option<&'a Arg> {
+    command.get_arguments().find(
+        |arg| match (word.strip_prefix("--"), word.strip_prefix('-')) {
+            (Some(long), _) => arg.get_long() == Some(long),
+            (None, Some(short)) => {
+                let mut chars = short.chars();
+                chars.next().is_some_and(|c| arg.get_short() == Some(c)) && chars.next().is_none()
+            }
+            _ => false,
+        },
+    )
+}
+
+/// Candidates for the value of a flag: its possible values, addon ids or addon names
+fn value_candidates(subcommand: Option<&str>, arg: &Arg, current: &str) -> (usize, Vec<Pair>) {
+    let possible_values = arg.get_possible_values();
+    if !possible_values.is_empty() {
+        let candidates = possible_values
+            .iter()
+            .filter(|value| value.get_name().starts_with(current))
+            .map(|value| described(value.get_name(), ""))
+            .collect();
+        return (0, candidates);
+    }
+
+    // installing commands offer what was searched for, the others what is installed
+    let addons = match subcommand {
+        Some("get") | Some("info") => {
+            let mut addons = searched_addons();
+            for addon in installed_addons() {
+                if !addons.iter().any(|(id, _)| *id == addon.0) {
+                    addons.push(addon);
+                }
+            }
+            addons
+        }
+        _ => installed_addons(),
+    };
+
+    match arg.get_id().as_str() {
+        "ids" | "id" => {
+            // only the id after the last comma of a list is completed
+            let start = current.rfind(',').map_or(0, |comma| comma + 1);
+            let partial = &current[start..];
+            let candidates = addons
+                .iter()
+                .filter(|(id, name)| {
+                    id.starts_with(partial) || name.to_lowercase().contains(&partial.to_lowercase())
+                })
+                .map(|(id, name)| Pair {
+                    display: format!("{:<10} {}", id, name),
+                    replacement: id.clone(),
+                })
+                .collect();
+            (start, candidates)
+        }
+        "name" | "filter" => {
+            let partial = current.trim_start_matches('"').to_lowercase();
+            let candidates = addons
+                .iter()
+                .filter(|(_, name)| name.to_lowercase().starts_with(&partial))
+                .map(|(_, name)| Pair {
+                    display: name.clone(),
+                    replacement: if name.contains(' ') {
+                        format!("\"{}\"", name)
+                    } else {
+                        name.clone()
+                    },
+                })
+                .collect();
+            (0, candidates)
+        }
+        _ => (0, Vec::new()),
+    }
+}
+
+fn described(replacement: &str, description: &str) -> Pair {
+    let display = if description.is_empty() {
+        replacement.to_string()
+    } else {
+        format!("{:<14} {}", replacement, description)
+    };
+    Pair {
+        display,
+        replacement: replacement.to_string(),
+    }
+}
+
+/// Splits the line before the cursor into the finished words and the start of the word
+/// being typed
+fn split_line(line: &str, pos: usize) -> (Vec<&str>, usize) {
+    let before_cursor = &line[..pos];
+    let start = before_cursor
+        .rfind(char::is_whitespace)
+        .map_or(0, |space| space + 1);
+    (before_cursor[..start].split_whitespace().collect(), start)
+}
+
+impl Completer for AddownHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (words, start) = split_line(line, pos);
+        let (offset, candidates) = self.candidates(&words, &line[start..pos]);
+        Ok((start + offset, candidates))
+    }
+}
+
+impl Hinter for AddownHelper {
+    type Hint = String;
+
+    /// Hints the rest of a command or flag once only one matches, otherwise the most recent
+    /// matching line from the history
+    fn hint(&self, line: &str, pos: usize, ctx: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+        let (words, start) = split_line(line, pos);
+        let current = &line[start..pos];
+        if !current.is_empty() {
+            let (offset, candidates) = self.candidates(&words, current);
+            if let [candidate] = candidates.as_slice() {
+                let typed = &current[offset..];
+                if let Some(rest) = candidate.replacement.strip_prefix(typed) {
+                    if !rest.is_empty() {
+                        return Some(rest.to_string());
+                    }
+                }
+            }
+        }
+
+        self.history_hinter.hint(line, pos, ctx)
+    }
+}
+
+impl Highlighter for AddownHelper {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("\x1b[2m{}\x1b[22m", hint))
+    }
+}
+
+impl Validator for AddownHelper {}
+
+impl Helper for AddownHelper {}
@@ -8,6 +8,21 @@ pub struct CurseForgeConfig {
     pub api_key: String,
     pub wow_path: String,
     pub path_suffix: String,
+    pub data_dir: String,
+    /// Backups kept per addon, 0 turns backups off
+    pub backup_count: usize,
+    /// Comma separated table columns to show, e.g. `id,name,version`
+    pub table_columns: Option<String>,
+    /// `wrap` or `truncate` for cells wider than their column
+    pub table_overflow: Option<String>,
+    /// Most lines kept in the interactive cli history
+    pub history_size: usize,
+    /// Needed to search and install addons from Wago
+    pub wago_api_key: Option<String>,
+    /// Least stable Wago release channel to install: `stable`, `beta` or `alpha`
+    pub wago_channel: String,
+    /// Optional, raises the GitHub api rate limit
+    pub github_token: Option<String>,
 }
 
 static CONFIG: OnceLock<CurseForgeConfig> = OnceLock::new();
@@ -18,10 +33,35 @@ impl CurseForgeConfig {
         let api_key = env::var("CURSEFORGE_API_KEY")
             .context("CURSEFORGE_API_KEY must be set in the environment")?;
 
+        // where addown keeps its own state such as the download cache
+        let data_dir = env::var("ADDOWN_DATA_DIR").unwrap_or_else(|_| {
+            env::var("HOME")
+                .map(|home| format!("{}/.addown", home))
+                .unwrap_or_else(|_| ".addown".to_string())
+        });
+
+        let backup_count = env::var("ADDOWN_BACKUP_COUNT")
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(3);
+
+        let history_size = env::var("ADDOWN_HISTORY_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(1000);
+
         Ok(CurseForgeConfig {
-            api_key: api_key,
+            api_key,
             wow_path: "/Applications/World of Warcraft".to_string(),
             path_suffix: "/_retail_/Interface/AddOns".to_string(),
+            data_dir,
+            backup_count,
+            table_columns: env::var("ADDOWN_TABLE_COLUMNS").ok(),
+            table_overflow: env::var("ADDOWN_TABLE_OVERFLOW").ok(),
+            history_size,
+            wago_api_key: env::var("WAGO_API_KEY").ok(),
+            wago_channel: env::var("ADDOWN_WAGO_CHANNEL").unwrap_or_else(|_| "stable".to_string()),
+            github_token: env::var("GITHUB_TOKEN").ok(),
         })
     }
 
@@ -29,7 +69,50 @@ impl CurseForgeConfig {
         format!("{}{}", self.wow_path, self.path_suffix)
     }
 
+    /// Where disabled addon folders are moved, next to AddOns so WoW doesn't load them
+    pub fn disabled_addons_path(&self) -> String {
+        format!("{}.disabled", self.addons_path())
+    }
+
+    pub fn wtf_path(&self) -> String {
+        format!("{}/_retail_/WTF", self.wow_path)
+    }
+
+    pub fn cache_path(&self) -> String {
+        format!("{}/cache", self.data_dir)
+    }
+
+    pub fn backups_path(&self) -> String {
+        format!("{}/backups", self.data_dir)
+    }
+
+    pub fn snapshots_path(&self) -> String {
+        format!("{}/snapshots", self.data_dir)
+    }
+
+    pub fn wtf_backups_path(&self) -> String {
+        format!("{}/wtf", self.data_dir)
+    }
+
+    pub fn groups_path(&self) -> String {
+        format!("{}/groups.json", self.data_dir)
+    }
+
+    pub fn history_path(&self) -> String {
+        format!("{}/history.txt", self.data_dir)
+    }
+
     pub fn get() -> &'static CurseForgeConfig {
         CONFIG.get_or_init(|| Self::from_env().expect("Failed to load configuration"))
     }
+
+    /// Loads the configuration like `get`, returning the error instead of panicking so
+    /// callers can report it before anything else needs the configuration
+    pub fn try_get() -> Result<&'static CurseForgeConfig> {
+        if let Some(config) = CONFIG.get() {
+            return Ok(config);
+        }
+        let config = Self::from_env()?;
+        Ok(CONFIG.get_or_init(|| config))
+    }
 }
@@ -1,8 +1,20 @@
+use crate::backup;
+use crate::cancellation::CancellationToken;
 use crate::config::CurseForgeConfig;
-use crate::models::{Game, GameArrayResponse, GameResponse, Mod, ModArrayResponse, ModResponse};
+use crate::groups;
+use crate::manifest::{Manifest, ManifestEntry};
+use crate::models::{
+    FingerprintMatch, FingerprintMatchesResponse, Game, GameArrayResponse, GameResponse, Mod,
+    ModArrayResponse, ModFile, ModFileArrayResponse, ModFileResponse, ModListResponse, ModResponse,
+    StringResponse,
+};
+use crate::providers::{self, curseforge};
 use std::fs::File;
 use std::io;
-use zip::ZipArchive;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
 pub async fn get_game_info(game_id: i32) -> Result<Game, Box<dyn std::error::Error>> {
     let config = CurseForgeConfig::get();
@@ -26,7 +38,7 @@ pub async fn get_game_info(game_id: i32) -> Result<Game, Box<dyn std::error::Err
 pub async fn get_games_info() -> Result<Vec<Game>, Box<dyn std::error::Error>> {
     let config = CurseForgeConfig::get();
     let client = reqwest::Client::new();
-    let url = format!("https://api.curseforge.com/v1/games");
+    let url = "https://api.curseforge.com/v1/games".to_string();
 
     let response = client
         .get(&url)
@@ -60,50 +72,353 @@ pub async fn get_mod_info(mod_id: u32) -> Result<Mod, Box<dyn std::error::Error>
     Ok(mod_info)
 }
 
-pub async fn get_mod_file(
+/// Fetches several mods in one request
+pub async fn get_mods(mod_ids: &[u32]) -> Result<Vec<Mod>, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let client = reqwest::Client::new();
+    let url = "https://api.curseforge.com/v1/mods".to_string();
+
+    let response = client
+        .post(&url)
+        .header("x-api-key", &config.api_key)
+        .header("accept", "application/json")
+        .json(&serde_json::json!({ "modIds": mod_ids }))
+        .send()
+        .await?;
+
+    let response_text = response.text().await?;
+    let mods_response: ModListResponse = serde_json::from_str(&response_text)?;
+    Ok(mods_response.data)
+}
+
+/// Looks up the files whose addon folders have these fingerprints
+pub async fn get_fingerprint_matches(
+    fingerprints: &[u32],
+) -> Result<Vec<FingerprintMatch>, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let client = reqwest::Client::new();
+    let url = "https://api.curseforge.com/v1/fingerprints".to_string();
+
+    let response = client
+        .post(&url)
+        .header("x-api-key", &config.api_key)
+        .header("accept", "application/json")
+        .json(&serde_json::json!({ "fingerprints": fingerprints }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let response_text = response.text().await?;
+    let matches_response: FingerprintMatchesResponse = serde_json::from_str(&response_text)?;
+    Ok(matches_response.data.exact_matches)
+}
+
+/// Files requested per page from the files endpoint
+const FILES_PAGE_SIZE: u32 = 50;
+
+/// Fetches the most recent files of a mod, newest first
+pub async fn get_mod_files(mod_id: u32) -> Result<Vec<ModFile>, Box<dyn std::error::Error>> {
+    let mut files = get_mod_files_page(mod_id, 0).await?.data;
+    files.sort_by_key(|file| std::cmp::Reverse(file.id));
+
+    Ok(files)
+}
+
+/// Fetches every file of a mod newer than `file_id`, newest first, reading pages until the
+/// page that reaches `file_id`
+pub async fn get_mod_files_since(
+    mod_id: u32,
+    file_id: u32,
+) -> Result<Vec<ModFile>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    let mut index = 0;
+    loop {
+        let page = get_mod_files_page(mod_id, index).await?;
+        let reached = page.data.iter().any(|file| file.id <= file_id);
+        index += page.pagination.result_count;
+        files.extend(page.data.into_iter().filter(|file| file.id > file_id));
+        if reached || page.pagination.result_count == 0 || index >= page.pagination.total_count {
+            break;
+        }
+    }
+    files.sort_by_key(|file| std::cmp::Reverse(file.id));
+
+    Ok(files)
+}
+
+async fn get_mod_files_page(
+    mod_id: u32,
+    index: u32,
+) -> Result<ModFileArrayResponse, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://api.curseforge.com/v1/mods/{}/files?index={}&pageSize={}",
+        mod_id, index, FILES_PAGE_SIZE
+    );
+
+    let response = client
+        .get(&url)
+        .header("x-api-key", &config.api_key)
+        .header("accept", "application/json")
+        .send()
+        .await?;
+
+    let response_text = response.text().await?;
+    Ok(serde_json::from_str(&response_text)?)
+}
+
+/// Fetches one file of a mod, however old it is
+pub async fn get_mod_file_info(
+    mod_id: u32,
+    file_id: u32,
+) -> Result<ModFile, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://api.curseforge.com/v1/mods/{}/files/{}",
+        mod_id, file_id
+    );
+
+    let response = client
+        .get(&url)
+        .header("x-api-key", &config.api_key)
+        .header("accept", "application/json")
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let response_text = response.text().await?;
+    let file_response: ModFileResponse = serde_json::from_str(&response_text)?;
+    Ok(file_response.data)
+}
+
+/// Fetches the description of a mod as html
+pub async fn get_mod_description(mod_id: u32) -> Result<String, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let client = reqwest::Client::new();
+    let url = format!("https://api.curseforge.com/v1/mods/{}/description", mod_id);
+
+    let response = client
+        .get(&url)
+        .header("x-api-key", &config.api_key)
+        .header("accept", "application/json")
+        .send()
+        .await?;
+
+    let response_text = response.text().await?;
+    let description_response: StringResponse = serde_json::from_str(&response_text)?;
+    Ok(description_response.data)
+}
+
+/// Fetches the changelog of a mod file as html
+pub async fn get_mod_file_changelog(
+    mod_id: u32,
     file_id: u32,
-    filename: &str,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let config = CurseForgeConfig::get();
-    let file_id_str = file_id.to_string();
-    let file_path = &(config.addons_path() + "/" + filename);
-    let prefix = &file_id_str[0..4];
-    let suffix = &file_id_str[4..];
-    let encoded_filename = urlencoding::encode(filename);
-
-    let download_url = format!(
-        "https://edge.forgecdn.net/files/{}/{}/{}",
-        prefix, suffix, encoded_filename
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://api.curseforge.com/v1/mods/{}/files/{}/changelog",
+        mod_id, file_id
     );
 
-    let response = reqwest::get(download_url).await.unwrap();
-    let bytes = response.bytes().await.unwrap();
-    std::fs::write(file_path, bytes).unwrap();
-    //println!("Top level dirs: {}", get_top_level_dirs(file_path)?.join(", "));
-    println!(
-        "Downloaded {} ({} bytes)",
-        file_path,
-        std::fs::metadata(file_path).unwrap().len()
+    let response = client
+        .get(&url)
+        .header("x-api-key", &config.api_key)
+        .header("accept", "application/json")
+        .send()
+        .await?;
+
+    let response_text = response.text().await?;
+    let changelog_response: StringResponse = serde_json::from_str(&response_text)?;
+    Ok(changelog_response.data)
+}
+
+/// Installs a CurseForge file of a mod
+pub async fn get_mod_file(
+    game_mod: &Mod,
+    mod_file: &ModFile,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    providers::install_file(
+        &curseforge::addon_info(game_mod),
+        game_mod.id,
+        &curseforge::addon_file(mod_file),
+        cancel,
+    )
+    .await
+}
+
+/// Removes the folders of an addon Addown installed, backing them up first so the removal
+/// can be rolled back
+pub fn uninstall_mod(mod_id: u32) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let mut manifest = Manifest::load()?;
+    let Some(entry) = manifest.find(mod_id).cloned() else {
+        return Err(format!("Addon {} was not installed by Addown", mod_id).into());
+    };
+    if entry.provider == providers::dev::NAME {
+        return Err(format!("{} is a dev link, use unlink to remove it", entry.name).into());
+    }
+    groups::check_enabled(mod_id)?;
+
+    backup::backup_installed(mod_id, &entry.directories)?;
+    for dir in &entry.directories {
+        let path = Path::new(&config.addons_path()).join(dir);
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        }
+    }
+    manifest.remove(mod_id);
+    manifest.save()?;
+
+    Ok(entry)
+}
+
+/// Installs an addon archive into AddOns, backing up the version it replaces.
+/// Returns the top level folders that were installed.
+pub fn install_archive(
+    archive_path: &Path,
+    mod_id: u32,
+    cancel: &CancellationToken,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    groups::check_enabled(mod_id)?;
+    let file_path = &archive_path.to_string_lossy().to_string();
+    let old_path = format!("{}/.addown-old-{}", config.addons_path(), mod_id);
+    if Path::new(&old_path).exists() {
+        return Err(format!(
+            "{} holds folders an earlier install could not put back, move them into AddOns or delete them",
+            old_path
+        )
+        .into());
+    }
+
+    // extract next to the addons so a cancelled install never leaves half written addon folders
+    let staging_path = format!("{}/.addown-staging-{}", config.addons_path(), mod_id);
+    let staged = cancel
+        .check()
+        .and_then(|_| unzip_file(file_path, &staging_path, cancel))
+        .and_then(|_| get_top_level_dirs(file_path))
+        .and_then(|dirs| backup::backup_installed(mod_id, &dirs).map(|_| dirs));
+    let dirs = match staged {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            std::fs::remove_dir_all(&staging_path).ok();
+            return Err(e);
+        }
+    };
+
+    // past this point the install is committed and is not interrupted
+    let previous_dirs = Manifest::load()?
+        .find(mod_id)
+        .map(|entry| entry.directories.clone())
+        .unwrap_or_default();
+    let installed = install_staged_dirs(
+        &dirs,
+        &previous_dirs,
+        &staging_path,
+        &old_path,
+        &config.addons_path(),
     );
+    std::fs::remove_dir_all(&staging_path).ok();
+    installed?;
 
-    for dir in get_top_level_dirs(file_path)? {
-        let dir_path = format!("{}/{}", config.addons_path(), dir);
-        std::fs::remove_dir_all(&dir_path).ok();
+    Ok(dirs)
+}
+
+/// Streams a download to disk, stopping as soon as the command is cancelled, even while
+/// waiting on a stalled connection
+pub(crate) async fn download_file(
+    url: &str,
+    file_path: &str,
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut response = cancel.run(reqwest::get(url)).await?.error_for_status()?;
+    let mut file = File::create(file_path)?;
+    while let Some(chunk) = cancel.run(response.chunk()).await? {
+        file.write_all(&chunk)?;
     }
 
-    unzip_file(file_path, &config.addons_path()).expect("TODO: panic message");
-    std::fs::remove_file(file_path).ok();
+    Ok(())
+}
 
-    Ok("ok".to_string()) // todo fix
+/// Replaces the addon folders in `addons_path` with the ones extracted to `staging_path`,
+/// removing folders from the previous version that are no longer part of the addon.
+/// The folders being replaced are first moved aside to `old_path` and only deleted once every
+/// staged folder is in place, if a move fails they are put back.
+fn install_staged_dirs(
+    dirs: &[String],
+    previous_dirs: &[String],
+    staging_path: &str,
+    old_path: &str,
+    addons_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut replaced: Vec<String> = previous_dirs
+        .iter()
+        .chain(dirs)
+        .filter(|dir| std::fs::symlink_metadata(format!("{}/{}", addons_path, dir)).is_ok())
+        .cloned()
+        .collect();
+    replaced.sort();
+    replaced.dedup();
+
+    std::fs::create_dir_all(old_path)?;
+    let mut moved_aside = Vec::new();
+    let mut moved_in = Vec::new();
+    let moved = move_dirs(&replaced, addons_path, old_path, &mut moved_aside)
+        .and_then(|_| move_dirs(dirs, staging_path, addons_path, &mut moved_in));
+    if let Err(e) = moved {
+        for dir in &moved_in {
+            std::fs::rename(
+                format!("{}/{}", addons_path, dir),
+                format!("{}/{}", staging_path, dir),
+            )
+            .ok();
+        }
+        for dir in &moved_aside {
+            std::fs::rename(
+                format!("{}/{}", old_path, dir),
+                format!("{}/{}", addons_path, dir),
+            )
+            .ok();
+        }
+        // only empty once every folder is back, otherwise it keeps what could not be restored
+        std::fs::remove_dir(old_path).ok();
+        return Err(e.into());
+    }
+    std::fs::remove_dir_all(old_path).ok();
+
+    Ok(())
 }
 
-pub fn unzip_file(zip_path: &str, extract_to: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// Renames each of `dirs` from `from` to `to`, recording the ones that were moved
+fn move_dirs(dirs: &[String], from: &str, to: &str, moved: &mut Vec<String>) -> io::Result<()> {
+    for dir in dirs {
+        std::fs::rename(format!("{}/{}", from, dir), format!("{}/{}", to, dir))?;
+        moved.push(dir.clone());
+    }
+
+    Ok(())
+}
+
+pub fn unzip_file(
+    zip_path: &str,
+    extract_to: &str,
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn std::error::Error>> {
     let file = File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
 
     for i in 0..archive.len() {
+        cancel.check()?;
         let mut file = archive.by_index(i)?;
-        let outpath = std::path::Path::new(extract_to).join(file.name());
+        // entries such as `../x` or absolute paths would be written outside `extract_to`
+        let Some(enclosed) = file.enclosed_name().map(Path::to_path_buf) else {
+            return Err(format!("{} has an unsafe entry '{}'", zip_path, file.name()).into());
+        };
+        let outpath = Path::new(extract_to).join(enclosed);
 
         if file.is_dir() {
             std::fs::create_dir_all(&outpath)?;
@@ -119,6 +434,49 @@ pub fn unzip_file(zip_path: &str, extract_to: &str) -> Result<(), Box<dyn std::e
     Ok(())
 }
 
+/// Writes `dirs` (relative to `base_dir`) and any `extra_files` into a new zip archive.
+/// Symlinks are left out.
+pub fn zip_dirs(
+    zip_path: &Path,
+    base_dir: &Path,
+    dirs: &[String],
+    extra_files: &[(&str, &[u8])],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = zip_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut zip = ZipWriter::new(File::create(zip_path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut pending: Vec<PathBuf> = dirs.iter().map(PathBuf::from).collect();
+    while let Some(relative) = pending.pop() {
+        let full_path = base_dir.join(&relative);
+        let name = relative.to_string_lossy().replace('\\', "/");
+        let metadata = std::fs::symlink_metadata(&full_path)?;
+        // links, such as dev links to working copies, point outside AddOns and aren't archived
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+        if metadata.is_dir() {
+            zip.add_directory(format!("{}/", name), options)?;
+            for child in std::fs::read_dir(&full_path)? {
+                pending.push(relative.join(child?.file_name()));
+            }
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut File::open(&full_path)?, &mut zip)?;
+        }
+    }
+
+    for (name, content) in extra_files {
+        zip.start_file(*name, options)?;
+        zip.write_all(content)?;
+    }
+    zip.finish()?;
+
+    Ok(())
+}
+
 pub fn get_top_level_dirs(zip_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     let file = File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
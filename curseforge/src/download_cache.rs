@@ -0,0 +1,293 @@
+use crate::config::CurseForgeConfig;
+use crate::providers::AddonFile;
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// An addon archive stored in the download cache
+pub struct CacheEntry {
+    /// Provider the file came from, empty for entries cached before providers were recorded
+    pub provider: String,
+    pub file_id: u32,
+    pub hash: String,
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+/// Directory for a cached file. Entries are keyed by provider, file id and content hash, as
+/// file ids are only unique within a provider and a re-uploaded file with the same id must
+/// never serve stale bytes.
+fn entry_dir(provider: &str, file_id: u32, hash: &str) -> PathBuf {
+    let config = CurseForgeConfig::get();
+    Path::new(&config.cache_path()).join(format!("{}-{}-{}", provider, file_id, hash))
+}
+
+/// The file name a provider reports, refused if it would place the file outside its cache entry
+fn checked_file_name(file: &AddonFile) -> Result<&str, Box<dyn std::error::Error>> {
+    let name = file.file_name.as_str();
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!("Refusing to cache a file named '{}'", name).into());
+    }
+
+    Ok(name)
+}
+
+/// Returns the cached archive for `file` of a provider if one exists and its contents still
+/// match. Files without an id or a hash can't be told apart and are never served from the cache.
+pub fn lookup(provider: &str, file: &AddonFile) -> Option<PathBuf> {
+    let file_name = checked_file_name(file).ok()?;
+    let entry = match (&file.sha1, file.id) {
+        (Some(hash), _) => entry_dir(provider, file.id, hash).join(file_name),
+        (None, 0) => return None,
+        (None, file_id) => find_file(provider, file_id)?.path,
+    };
+    if !entry.is_file() {
+        return None;
+    }
+
+    if let Some(hash) = &file.sha1 {
+        if !sha1_file(&entry).is_ok_and(|actual| actual.eq_ignore_ascii_case(hash)) {
+            fs::remove_dir_all(entry.parent()?).ok();
+            return None;
+        }
+    }
+
+    touch(&entry);
+    Some(entry)
+}
+
+/// Returns any cached archive for a file id of a provider, regardless of hash
+pub fn find_file(provider: &str, file_id: u32) -> Option<CacheEntry> {
+    list_entries()
+        .ok()?
+        .into_iter()
+        .find(|entry| entry.provider == provider && entry.file_id == file_id)
+}
+
+/// Path a download for `file` is written to before it is verified and added to the cache
+pub fn partial_path(
+    provider: &str,
+    file: &AddonFile,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let file_name = checked_file_name(file)?;
+    fs::create_dir_all(config.cache_path())?;
+    Ok(
+        Path::new(&config.cache_path())
+            .join(format!("{}-{}-{}.part", provider, file.id, file_name)),
+    )
+}
+
+/// Verifies a finished download against the hash its provider reports and moves it into
+/// the cache
+pub fn store(
+    provider: &str,
+    file: &AddonFile,
+    downloaded: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let file_name = checked_file_name(file)?;
+    let actual = sha1_file(downloaded)?;
+    if let Some(expected) = &file.sha1 {
+        if !actual.eq_ignore_ascii_case(expected) {
+            fs::remove_file(downloaded).ok();
+            return Err(format!(
+                "Downloaded {} does not match its sha1 hash (expected {}, got {})",
+                file.file_name, expected, actual
+            )
+            .into());
+        }
+    }
+
+    let dir = entry_dir(provider, file.id, &actual);
+    fs::create_dir_all(&dir)?;
+    let entry = dir.join(file_name);
+    fs::rename(downloaded, &entry)?;
+
+    Ok(entry)
+}
+
+/// Lists every archive in the cache, most recently used first
+pub fn list_entries() -> Result<Vec<CacheEntry>, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let mut entries = Vec::new();
+    let dirs = match fs::read_dir(config.cache_path()) {
+        Ok(dirs) => dirs,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e.into()),
+    };
+
+    for dir in dirs {
+        let dir = dir?;
+        if !dir.path().is_dir() {
+            continue;
+        }
+        let dir_name = dir.file_name().to_string_lossy().to_string();
+        // `<provider>-<file id>-<hash>`, or `<file id>-<hash>` from before providers were recorded
+        let parts: Vec<&str> = dir_name.splitn(3, '-').collect();
+        let (provider, file_id, hash) = match parts[..] {
+            [provider, file_id, hash] => (provider, file_id, hash),
+            [file_id, hash] => ("", file_id, hash),
+            _ => continue,
+        };
+        let Ok(file_id) = file_id.parse::<u32>() else {
+            continue;
+        };
+
+        for file in fs::read_dir(dir.path())? {
+            let file = file?;
+            let metadata = file.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            entries.push(CacheEntry {
+                provider: provider.to_string(),
+                file_id,
+                hash: hash.to_string(),
+                file_name: file.file_name().to_string_lossy().to_string(),
+                path: file.path(),
+                size: metadata.len(),
+                last_used: metadata.modified()?,
+            });
+        }
+    }
+
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used));
+    Ok(entries)
+}
+
+/// Removes cache entries that have not been used for longer than `max_age`.
+/// Returns the number of entries and bytes removed.
+pub fn prune(max_age: Duration) -> Result<(usize, u64), Box<dyn std::error::Error>> {
+    let now = SystemTime::now();
+    let mut removed = 0;
+    let mut freed = 0;
+    for entry in list_entries()? {
+        let age = now.duration_since(entry.last_used).unwrap_or_default();
+        if age > max_age {
+            if let Some(dir) = entry.path.parent() {
+                fs::remove_dir_all(dir)?;
+            }
+            removed += 1;
+            freed += entry.size;
+        }
+    }
+
+    Ok((removed, freed))
+}
+
+/// Parses ages like `30d`, `12h`, `2w`, `90m` or `45s`. A bare number is treated as days.
+pub fn parse_age(age: &str) -> Result<Duration, Box<dyn std::error::Error>> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (amount, unit) = age.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid age '{}', expected something like 30d", age))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(format!("Invalid age unit '{}', use s, m, h, d or w", unit).into()),
+    };
+
+    let seconds = amount
+        .checked_mul(seconds)
+        .ok_or_else(|| format!("Age '{}' is too long", age))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Formats a byte count for display, e.g. `12.4 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn sha1_file(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Marks an entry as recently used so `cache prune` keeps it
+fn touch(path: &Path) {
+    if let Ok(file) = File::options().append(true).open(path) {
+        file.set_modified(SystemTime::now()).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_age_reads_units() {
+        assert_eq!(parse_age("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_age("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 60 * 60));
+        assert_eq!(
+            parse_age(" 2w ").unwrap(),
+            Duration::from_secs(14 * 24 * 60 * 60)
+        );
+        assert_eq!(parse_age("30").unwrap(), parse_age("30d").unwrap());
+    }
+
+    #[test]
+    fn parse_age_rejects_bad_input() {
+        assert!(parse_age("").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+        assert!(parse_age("99999999999999w").is_err());
+    }
+
+    #[test]
+    fn checked_file_name_refuses_paths() {
+        let file = |file_name: &str| AddonFile {
+            id: 1,
+            display_name: String::new(),
+            file_name: file_name.to_string(),
+            download_url: String::new(),
+            sha1: None,
+            game_versions: Vec::new(),
+            released: String::new(),
+            release_tag: None,
+        };
+        assert_eq!(
+            checked_file_name(&file("Details-1.2.zip")).unwrap(),
+            "Details-1.2.zip"
+        );
+        assert!(checked_file_name(&file("")).is_err());
+        assert!(checked_file_name(&file("../manifest.json")).is_err());
+        assert!(checked_file_name(&file("sub/addon.zip")).is_err());
+        assert!(checked_file_name(&file("sub\\addon.zip")).is_err());
+    }
+
+    #[test]
+    fn format_size_picks_a_unit() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
@@ -0,0 +1,253 @@
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Seed CurseForge hashes files with
+const SEED: u32 = 1;
+
+/// CurseForge's fingerprint of an addon folder: the MurmurHash2 of the sorted fingerprints
+/// of its tocs, `Bindings.xml` and every file they include
+pub fn folder_fingerprint(dir: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    let folder = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut files = BTreeSet::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name == "bindings.xml" || is_toc_of(&name, &folder) {
+            collect_includes(&path, &mut files)?;
+        }
+    }
+
+    let mut fingerprints = Vec::new();
+    for file in &files {
+        fingerprints.push(file_fingerprint(&fs::read(file)?));
+    }
+    fingerprints.sort();
+    let joined: String = fingerprints.iter().map(u32::to_string).collect();
+    Ok(murmur2(joined.as_bytes(), SEED))
+}
+
+/// MurmurHash2 of a file with its whitespace removed, the way CurseForge hashes files
+fn file_fingerprint(content: &[u8]) -> u32 {
+    let normalized: Vec<u8> = content
+        .iter()
+        .copied()
+        .filter(|byte| !matches!(byte, b'\t' | b'\n' | b'\r' | b' '))
+        .collect();
+    murmur2(&normalized, SEED)
+}
+
+/// Whether a file name is a toc for the folder, with or without a flavor suffix
+fn is_toc_of(name: &str, folder: &str) -> bool {
+    let Some(stem) = name.strip_suffix(".toc") else {
+        return false;
+    };
+    let Some(suffix) = stem.strip_prefix(folder) else {
+        return false;
+    };
+    suffix.is_empty()
+        || suffix
+            .strip_prefix(['-', '_'])
+            .is_some_and(|flavor| !flavor.is_empty() && flavor.chars().all(char::is_alphabetic))
+}
+
+/// Adds a file and, for tocs and xml, everything it includes
+fn collect_includes(
+    path: &Path,
+    files: &mut BTreeSet<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = find_case_insensitive(path, false) else {
+        return Ok(());
+    };
+    if !files.insert(path.clone()) {
+        return Ok(());
+    }
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let includes: Vec<String> = match extension.as_str() {
+        "toc" => fs::read_to_string(&path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        "xml" => {
+            let content = fs::read_to_string(&path)?;
+            let content = xml_comment_regex().replace_all(&content, "");
+            xml_include_regex()
+                .captures_iter(&content)
+                .map(|captures| captures[1].to_string())
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    for include in includes {
+        let relative: PathBuf = include.split(['\\', '/']).collect();
+        collect_includes(&dir.join(relative), files)?;
+    }
+    Ok(())
+}
+
+/// WoW treats file names case insensitively, so tocs don't always match the files on disk
+fn find_case_insensitive(path: &Path, is_dir: bool) -> Option<PathBuf> {
+    let is_kind = |path: &Path| {
+        if is_dir {
+            path.is_dir()
+        } else {
+            path.is_file()
+        }
+    };
+    if is_kind(path) {
+        return Some(path.to_path_buf());
+    }
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let parent = find_case_insensitive(path.parent()?, true)?;
+    fs::read_dir(parent)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|candidate| {
+            is_kind(candidate)
+                && candidate
+                    .file_name()
+                    .is_some_and(|candidate| candidate.to_string_lossy().to_lowercase() == name)
+        })
+}
+
+fn xml_comment_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?s)<!--.*?-->").unwrap())
+}
+
+fn xml_include_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"(?i)<(?:Include|Script)\s+file\s*=\s*["']([^"']+)["']"#).unwrap()
+    })
+}
+
+/// 32 bit MurmurHash2
+fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut hash = seed ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M) ^ k;
+    }
+
+    let rest = chunks.remainder();
+    if rest.len() == 3 {
+        hash ^= (rest[2] as u32) << 16;
+    }
+    if rest.len() >= 2 {
+        hash ^= (rest[1] as u32) << 8;
+    }
+    if !rest.is_empty() {
+        hash ^= rest[0] as u32;
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn murmur2_passes_the_smhasher_verification() {
+        // SMHasher hashes keys of 0 to 255 bytes with seed 256 - length, then the hashes
+        let key: Vec<u8> = (0..=255).collect();
+        let mut hashes = Vec::new();
+        for len in 0..256 {
+            hashes.extend(murmur2(&key[..len], 256 - len as u32).to_le_bytes());
+        }
+        assert_eq!(murmur2(&hashes, 0), 0x2786_4C1E);
+    }
+
+    #[test]
+    fn murmur2_hashes_tails_of_every_length() {
+        assert_eq!(murmur2(b"", SEED), 1_540_447_798);
+        assert_eq!(murmur2(b"abc", SEED), 1_621_425_345);
+        assert_eq!(
+            murmur2(b"The quick brown fox jumps over the lazy dog", SEED),
+            504_383_975
+        );
+    }
+
+    #[test]
+    fn file_fingerprint_ignores_whitespace() {
+        assert_eq!(file_fingerprint(b"a b\r\n\tc\n"), murmur2(b"abc", SEED));
+        assert_eq!(
+            file_fingerprint(b"local x = 1\n"),
+            file_fingerprint(b"local  x=1\r\n\r\n")
+        );
+    }
+
+    #[test]
+    fn is_toc_of_accepts_flavor_suffixes() {
+        assert!(is_toc_of("myaddon.toc", "myaddon"));
+        assert!(is_toc_of("myaddon_mainline.toc", "myaddon"));
+        assert!(is_toc_of("myaddon-classic.toc", "myaddon"));
+        assert!(!is_toc_of("myaddon_options.lua", "myaddon"));
+        assert!(!is_toc_of("myaddon_2.toc", "myaddon"));
+        assert!(!is_toc_of("other.toc", "myaddon"));
+    }
+
+    #[test]
+    fn folder_fingerprint_covers_included_files_only() {
+        let dir = TempDir::new("fingerprint");
+        let addon = dir.join("MyAddon");
+        fs::create_dir_all(addon.join("Libs")).unwrap();
+        fs::write(
+            addon.join("MyAddon.toc"),
+            "## Title: My Addon\nCore.lua\nLibs\\Lib.xml\n",
+        )
+        .unwrap();
+        fs::write(addon.join("Core.lua"), "print('core')").unwrap();
+        fs::write(
+            addon.join("Libs").join("Lib.xml"),
+            "<Ui><!-- <Script file=\"Old.lua\"/> --><Script file=\"Lib.lua\"/></Ui>",
+        )
+        .unwrap();
+        fs::write(addon.join("Libs").join("lib.lua"), "print('lib')").unwrap();
+        fs::write(addon.join("Notes.txt"), "not loaded").unwrap();
+
+        let mut fingerprints: Vec<u32> =
+            ["MyAddon.toc", "Core.lua", "Libs/Lib.xml", "Libs/lib.lua"]
+                .iter()
+                .map(|file| file_fingerprint(&fs::read(addon.join(file)).unwrap()))
+                .collect();
+        fingerprints.sort();
+        let joined: String = fingerprints.iter().map(u32::to_string).collect();
+        let expected = murmur2(joined.as_bytes(), SEED);
+        assert_eq!(folder_fingerprint(&addon).unwrap(), expected);
+
+        fs::write(addon.join("Notes.txt"), "changed").unwrap();
+        assert_eq!(folder_fingerprint(&addon).unwrap(), expected);
+        fs::write(addon.join("Core.lua"), "print('changed')").unwrap();
+        assert_ne!(folder_fingerprint(&addon).unwrap(), expected);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::{CurseForgeConfig, Mod, ModFile};
+use crate::{CurseForgeConfig, FileIndex, Mod, ModFile};
 use once_cell::sync::Lazy;
 use roxmltree::Document;
+use std::cmp::Ordering;
 use std::fs;
 
 static GAME_VERSION: Lazy<Result<String, String>> =
@@ -26,7 +27,7 @@ fn load_game_version() -> Result<String, Box<dyn std::error::Error>> {
 }
 
 fn get_version_from_info_plist(plist_path: &String) -> Result<String, Box<dyn std::error::Error>> {
-    let xml_content = fs::read_to_string(&plist_path)?;
+    let xml_content = fs::read_to_string(plist_path)?;
     let doc = Document::parse_with_options(
         &xml_content,
         roxmltree::ParsingOptions {
@@ -57,7 +58,7 @@ fn get_version_from_info_plist(plist_path: &String) -> Result<String, Box<dyn st
 fn get_version_from_build_info(
     build_info_path: &String,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(&build_info_path)?;
+    let content = fs::read_to_string(build_info_path)?;
     let mut header_row = true;
     let mut version_header_index = 0;
     for line in content.lines() {
@@ -99,9 +100,9 @@ fn get_game_version() -> Result<&'static str, Box<dyn std::error::Error>> {
         .map_err(|e| e.clone().into())
 }
 
-pub(crate) fn get_mod_file_for_game_version<'a>(
-    game_mod: &'a Mod,
-) -> Result<&'a ModFile, Box<dyn std::error::Error>> {
+pub fn get_mod_file_for_game_version(
+    game_mod: &Mod,
+) -> Result<&ModFile, Box<dyn std::error::Error>> {
     let game_version = get_game_version()?;
 
     for file in &game_mod.latest_files {
@@ -114,3 +115,46 @@ pub(crate) fn get_mod_file_for_game_version<'a>(
 
     Err("File not found for game version".into())
 }
+
+/// Whether a game version is the one installed
+pub fn is_installed_game_version(version: &str) -> bool {
+    get_game_version().is_ok_and(|installed| installed == version)
+}
+
+/// The newest file for each game version a mod supports, newest game version first
+pub fn latest_files_by_game_version(game_mod: &Mod) -> Vec<&FileIndex> {
+    let mut latest: Vec<&FileIndex> = Vec::new();
+    for index in &game_mod.latest_files_indexes {
+        match latest
+            .iter_mut()
+            .find(|file| file.game_version == index.game_version)
+        {
+            Some(file) if index.file_id > file.file_id => *file = index,
+            Some(_) => {}
+            None => latest.push(index),
+        }
+    }
+
+    latest.sort_by(|a, b| compare_versions(&b.game_version, &a.game_version));
+    latest
+}
+
+pub fn release_type_name(release_type: u32) -> &'static str {
+    match release_type {
+        1 => "Release",
+        2 => "Beta",
+        3 => "Alpha",
+        _ => "Unknown",
+    }
+}
+
+/// Compares dotted versions like `11.0.2` part by part
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parts(a).cmp(&parts(b))
+}
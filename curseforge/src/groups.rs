@@ -0,0 +1,213 @@
+use crate::addons_txt;
+use crate::config::CurseForgeConfig;
+use crate::manifest::Manifest;
+use crate::wtf::WtfFilter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Named sets of installed addons, stored as json in the data dir
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Groups {
+    pub groups: BTreeMap<String, Group>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Group {
+    /// Manifest ids of the addons in the group
+    pub addons: Vec<u32>,
+    /// Whether the group's folders have been moved out of AddOns
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+impl Groups {
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        match fs::read_to_string(CurseForgeConfig::get().groups_path()) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes a temp file and renames it, so a failed save leaves the previous groups intact
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let config = CurseForgeConfig::get();
+        fs::create_dir_all(&config.data_dir)?;
+        let path = config.groups_path();
+        let temp_path = format!("{}.tmp", path);
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        if let Err(e) = fs::rename(&temp_path, &path) {
+            fs::remove_file(&temp_path).ok();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn get_mut(&mut self, name: &str) -> Result<&mut Group, Box<dyn std::error::Error>> {
+        self.groups
+            .get_mut(name)
+            .ok_or_else(|| format!("No group named '{}'", name).into())
+    }
+}
+
+/// The disabled group an addon belongs to. Its folders are out of AddOns, so installing or
+/// removing the addon has to wait until the group is enabled again.
+pub fn disabled_group(mod_id: u32) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    Ok(Groups::load()?
+        .groups
+        .into_iter()
+        .find(|(_, group)| group.disabled && group.addons.contains(&mod_id))
+        .map(|(name, _)| name))
+}
+
+/// Fails when an addon belongs to a disabled group
+pub fn check_enabled(mod_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+    match disabled_group(mod_id)? {
+        Some(name) => Err(format!(
+            "Addon {} is in the disabled group '{}', enable the group first",
+            mod_id, name
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// Adds installed addons to a group, creating it if needed
+pub fn add_to_group(name: &str, ids: &[u32]) -> Result<Group, Box<dyn std::error::Error>> {
+    if name.trim().is_empty() {
+        return Err("Group names can't be empty".into());
+    }
+    let manifest = Manifest::load()?;
+    if let Some(id) = ids.iter().find(|id| manifest.find(**id).is_none()) {
+        return Err(format!("Addon {} was not installed by Addown", id).into());
+    }
+
+    let mut groups = Groups::load()?;
+    let group = groups.groups.entry(name.to_string()).or_default();
+    if group.disabled {
+        return Err(format!("Group '{}' is disabled, enable it before changing it", name).into());
+    }
+    for id in ids {
+        if !group.addons.contains(id) {
+            group.addons.push(*id);
+        }
+    }
+    let group = group.clone();
+    groups.save()?;
+
+    Ok(group)
+}
+
+/// Removes addons from a group, or the whole group when no ids are given. Returns what is
+/// left of the group.
+pub fn remove_from_group(
+    name: &str,
+    ids: Option<&[u32]>,
+) -> Result<Option<Group>, Box<dyn std::error::Error>> {
+    let mut groups = Groups::load()?;
+    let group = groups.get_mut(name)?;
+    if group.disabled {
+        return Err(format!("Group '{}' is disabled, enable it before changing it", name).into());
+    }
+    let remaining = match ids {
+        Some(ids) => {
+            group.addons.retain(|id| !ids.contains(id));
+            Some(group.clone())
+        }
+        None => {
+            groups.groups.remove(name);
+            None
+        }
+    };
+    groups.save()?;
+
+    Ok(remaining)
+}
+
+/// Enables or disables the addons of a group. With a filter the AddOns.txt of the matching
+/// characters is written, otherwise the folders are moved out of AddOns and back. Returns
+/// the folders that were changed.
+pub fn set_group_enabled(
+    name: &str,
+    enabled: bool,
+    characters: Option<&WtfFilter>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let manifest = Manifest::load()?;
+    let mut groups = Groups::load()?;
+    let group = groups.get_mut(name)?;
+
+    let mut folders = Vec::new();
+    let mut missing = Vec::new();
+    for id in &group.addons {
+        match manifest.find(*id) {
+            Some(entry) => folders.extend(entry.directories.iter().cloned()),
+            None => missing.push(id.to_string()),
+        }
+    }
+
+    if let Some(filter) = characters {
+        // AddOns.txt is per character, so a missing addon would silently stay in its old state
+        if !missing.is_empty() {
+            return Err(format!(
+                "Addon(s) {} in group '{}' are no longer installed, remove them from the group first",
+                missing.join(", "),
+                name
+            )
+            .into());
+        }
+        for path in addons_txt::set_enabled(filter, &folders, enabled)? {
+            eprintln!("Wrote {}", path.display());
+        }
+        return Ok(folders);
+    }
+    for id in &missing {
+        eprintln!("Addon {} in group '{}' is no longer installed", id, name);
+    }
+
+    let (from, to) = if enabled {
+        (config.disabled_addons_path(), config.addons_path())
+    } else {
+        (config.addons_path(), config.disabled_addons_path())
+    };
+    // folders shared with another group may already have been moved
+    let folders: Vec<String> = folders
+        .into_iter()
+        .filter(|folder| fs::symlink_metadata(Path::new(&from).join(folder)).is_ok())
+        .collect();
+    // nothing is replaced, a folder in the way is newer than the one that was moved out
+    let conflicts: Vec<&str> = folders
+        .iter()
+        .filter(|folder| fs::symlink_metadata(Path::new(&to).join(folder)).is_ok())
+        .map(String::as_str)
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(format!("Already in {}: {}", to, conflicts.join(", ")).into());
+    }
+
+    // put back what was moved so the folders match the saved state of the group
+    let move_back = |moved: &[String]| {
+        for folder in moved.iter().rev() {
+            fs::rename(Path::new(&to).join(folder), Path::new(&from).join(folder)).ok();
+        }
+    };
+    fs::create_dir_all(&to)?;
+    let mut moved = Vec::new();
+    for folder in folders {
+        if let Err(e) = fs::rename(Path::new(&from).join(&folder), Path::new(&to).join(&folder)) {
+            move_back(&moved);
+            return Err(format!("Could not move {}: {}", folder, e).into());
+        }
+        moved.push(folder);
+    }
+    group.disabled = !enabled;
+    if let Err(e) = groups.save() {
+        move_back(&moved);
+        return Err(format!("Could not save groups, the folders were moved back: {}", e).into());
+    }
+
+    Ok(moved)
+}
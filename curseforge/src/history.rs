@@ -0,0 +1,67 @@
+use crate::config::CurseForgeConfig;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Lines entered in the interactive cli, saved in the data dir between sessions.
+/// Every line is kept once, at the position it was last used.
+pub struct CommandHistory {
+    lines: Vec<String>,
+    path: PathBuf,
+    max_size: usize,
+}
+
+impl CommandHistory {
+    /// Loads the saved history, or starts an empty one when there is none
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let config = CurseForgeConfig::get();
+        let path = PathBuf::from(config.history_path());
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut history = Self {
+            lines: Vec::new(),
+            path,
+            max_size: config.history_size,
+        };
+        for line in content.lines() {
+            history.push(line);
+        }
+
+        Ok(history)
+    }
+
+    /// Oldest line first
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Adds a line and saves the history
+    pub fn add(&mut self, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        self.push(line);
+        self.save()
+    }
+
+    fn push(&mut self, line: &str) {
+        self.lines.retain(|existing| existing != line);
+        self.lines.push(line.to_string());
+        let overflow = self.lines.len().saturating_sub(self.max_size);
+        self.lines.drain(..overflow);
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = self.lines.join("\n");
+        content.push('\n');
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+/// Tags whose content is never shown
+const HIDDEN_TAGS: [&str; 3] = ["script", "style", "head"];
+/// Tags that start and end on their own line
+const BLOCK_TAGS: [&str; 16] = [
+    "p",
+    "div",
+    "br",
+    "hr",
+    "ul",
+    "ol",
+    "li",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "tr",
+    "pre",
+    "blockquote",
+];
+
+/// Converts the html CurseForge returns for descriptions and changelogs to plain text.
+/// Block elements become lines, list items get a `- ` marker and links keep their target
+/// when it differs from the link text.
+pub fn to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut hidden_depth = 0;
+    let mut preformatted = false;
+    let mut link: Option<(String, usize)> = None;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            push_text(&mut text, rest, hidden_depth, preformatted);
+            break;
+        };
+        push_text(&mut text, &rest[..tag_start], hidden_depth, preformatted);
+        rest = &rest[tag_start..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..tag_end];
+        rest = &rest[tag_end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if HIDDEN_TAGS.contains(&name.as_str()) {
+            if closing {
+                hidden_depth = hidden_depth.saturating_sub(1);
+            } else if !tag.ends_with('/') {
+                hidden_depth += 1;
+            }
+            continue;
+        }
+        if hidden_depth > 0 {
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("li", false) => {
+                new_line(&mut text);
+                text.push_str("- ");
+            }
+            ("pre", _) => {
+                preformatted = !closing;
+                new_line(&mut text);
+            }
+            ("a", false) => {
+                link = attribute(tag, "href").map(|href| (href, text.len()));
+            }
+            ("a", true) => {
+                if let Some((href, start)) = link.take() {
+                    if !href.is_empty() && text[start..].trim() != href {
+                        text.push_str(&format!(" ({})", href));
+                    }
+                }
+            }
+            (name, _) if BLOCK_TAGS.contains(&name) => new_line(&mut text),
+            _ => {}
+        }
+    }
+
+    tidy_lines(&text)
+}
+
+/// Appends text outside of tags, collapsing whitespace the way a browser would
+fn push_text(text: &mut String, raw: &str, hidden_depth: usize, preformatted: bool) {
+    if hidden_depth > 0 || raw.is_empty() {
+        return;
+    }
+    let decoded = decode_entities(raw);
+    if preformatted {
+        text.push_str(&decoded);
+        return;
+    }
+
+    let starts_with_space = decoded.starts_with(char::is_whitespace);
+    let ends_with_space = decoded.ends_with(char::is_whitespace);
+    let words: Vec<&str> = decoded.split_whitespace().collect();
+    let at_line_start = text.is_empty() || text.ends_with('\n') || text.ends_with(' ');
+    if words.is_empty() {
+        if !at_line_start {
+            text.push(' ');
+        }
+        return;
+    }
+    if starts_with_space && !at_line_start {
+        text.push(' ');
+    }
+    text.push_str(&words.join(" "));
+    if ends_with_space {
+        text.push(' ');
+    }
+}
+
+fn new_line(text: &mut String) {
+    let trimmed = text.trim_end_matches(' ').len();
+    text.truncate(trimmed);
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Trims every line and keeps at most one blank line between paragraphs
+fn tidy_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(if line.trim().is_empty() { "" } else { line });
+    }
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Reads a quoted attribute of a tag, e.g. `href` of `a href="..."`
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    // ascii lowercasing keeps byte offsets, so they can be used to slice the tag
+    let lower = tag.to_ascii_lowercase();
+    let start = lower.find(&format!("{}=", name))? + name.len() + 1;
+    let value = &tag[start..];
+    let value = match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+        _ => value.split(|c: char| c.is_whitespace()).next()?,
+    };
+    Some(decode_entities(value))
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|ch| (ch, end)));
+        match entity {
+            Some((ch, end)) => {
+                decoded.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "ndash" => Some('–'),
+        "mdash" => Some('—'),
+        "hellip" => Some('…'),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_reads_quoted_and_bare_values() {
+        assert_eq!(
+            attribute(r#"a HREF="https://example.com/?a=1&amp;b=2""#, "href").as_deref(),
+            Some("https://example.com/?a=1&b=2")
+        );
+        assert_eq!(
+            attribute("a href='x' title=y", "title").as_deref(),
+            Some("y")
+        );
+        assert_eq!(attribute("a title=y", "href"), None);
+    }
+
+    #[test]
+    fn attribute_keeps_offsets_with_non_ascii_text() {
+        // `İ` lowercases to more bytes than it has, which shifted the offsets
+        assert_eq!(
+            attribute(r#"a title="İİİ" href="x""#, "href").as_deref(),
+            Some("x")
+        );
+    }
+
+    #[test]
+    fn to_text_keeps_link_targets_and_list_items() {
+        assert_eq!(
+            to_text(r#"<p>See <a href="https://x.io">docs</a></p><ul><li>one</li></ul>"#),
+            "See docs (https://x.io)\n- one"
+        );
+    }
+}
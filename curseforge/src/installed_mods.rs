@@ -1,16 +1,206 @@
+use crate::cancellation::CancellationToken;
 use crate::config::CurseForgeConfig;
 use crate::curseforge_api;
+use crate::game_version;
+use crate::manifest::Manifest;
 use crate::mod_table::*;
+use crate::providers::curseforge::{self, WOW_GAME_ID};
+use crate::providers::{self, dev, wago, wowinterface, AddonFile, DEFAULT_PROVIDER};
+use clap::ValueEnum;
 use regex::Regex;
+use serde::Serialize;
 use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
 
-pub async fn get_installed_mods() -> Result<(), Box<dyn std::error::Error>> {
+/// An addon found in the AddOns folder
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledAddon {
+    pub id: u32,
+    /// Provider the addon came from, CurseForge unless Addown installed it from another one
+    /// or its toc only has another provider's id
+    pub provider: String,
+    /// Id of the addon at its provider
+    pub source_id: String,
+    pub name: String,
+    pub version: String,
+    pub notes: String,
+    pub folder: String,
+    /// Working copy the folder is linked to, for addons linked with `link`
+    pub linked_to: Option<String>,
+    pub size_bytes: u64,
+    /// When Addown installed the addon, or when its toc last changed for addons it didn't install
+    pub updated: Option<u64>,
+    /// Newest version for the current game version, only looked up for `view --outdated`
+    pub latest_version: Option<String>,
+    pub outdated: Option<bool>,
+}
+
+/// Order of the installed addons in `view`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ViewSort {
+    #[default]
+    Name,
+    Version,
+    /// Most recently updated first
+    Updated,
+    Id,
+    /// Largest first
+    Size,
+}
+
+/// Which installed addons `view` shows and in what order
+#[derive(Debug, Clone, Default)]
+pub struct ViewOptions {
+    pub sort: ViewSort,
+    /// Text to look for in the name, folder, notes or id
+    pub filter: Option<String>,
+    /// Only addons with a newer version for the current game version
+    pub outdated: bool,
+    /// Only addons that could not be matched to a CurseForge project
+    pub unknown: bool,
+}
+
+/// Builds a table of the installed addons, ready to print or page through
+pub fn get_installed_mods(
+    addons: &[InstalledAddon],
+) -> Result<ModTable, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    println!("{}", config.addons_path());
+
+    let mut mod_table = ModTable::new();
+    mod_table.populate_installed_mods_table(addons)?;
+
+    Ok(mod_table)
+}
+
+/// Scans the installed addons and applies the filters and sort order of `view`.
+/// With `outdated` the latest file of every matched addon is looked up.
+pub async fn view_installed_mods(
+    options: &ViewOptions,
+    cancel: &CancellationToken,
+) -> Result<Vec<InstalledAddon>, Box<dyn std::error::Error>> {
+    let mut addons = scan_installed_mods().await?;
+
+    if options.unknown {
+        addons.retain(|addon| addon.id == 0);
+    }
+    if let Some(filter) = &options.filter {
+        let filter = filter.to_lowercase();
+        addons.retain(|addon| {
+            addon.name.to_lowercase().contains(&filter)
+                || addon.folder.to_lowercase().contains(&filter)
+                || addon.notes.to_lowercase().contains(&filter)
+                || addon.id.to_string() == filter
+        });
+    }
+    if options.outdated {
+        check_latest_versions(&mut addons, cancel).await?;
+        addons.retain(|addon| addon.outdated == Some(true));
+    }
+
+    match options.sort {
+        ViewSort::Name => addons.sort_by_key(|addon| addon.name.to_lowercase()),
+        ViewSort::Version => addons.sort_by(|a, b| compare_versions(&a.version, &b.version)),
+        ViewSort::Updated => addons.sort_by_key(|addon| std::cmp::Reverse(addon.updated)),
+        ViewSort::Id => addons.sort_by_key(|addon| addon.id),
+        ViewSort::Size => addons.sort_by_key(|addon| std::cmp::Reverse(addon.size_bytes)),
+    }
+
+    Ok(addons)
+}
+
+/// A run of digits or of anything else in a version
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum VersionPart<'a> {
+    Number(u64),
+    Text(&'a str),
+}
+
+/// Compares versions by their numbers, so `10.0.2` comes after `9.2.0`. The text between
+/// numbers is compared as it is, and versions that still tie fall back to string order.
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    fn parts(version: &str) -> Vec<VersionPart<'_>> {
+        let mut parts = Vec::new();
+        let mut rest = version;
+        while let Some(first) = rest.chars().next() {
+            let digits = first.is_ascii_digit();
+            let end = rest
+                .find(|c: char| c.is_ascii_digit() != digits)
+                .unwrap_or(rest.len());
+            let (part, remainder) = rest.split_at(end);
+            parts.push(match part.parse() {
+                Ok(number) if digits => VersionPart::Number(number),
+                _ => VersionPart::Text(part),
+            });
+            rest = remainder;
+        }
+        parts
+    }
+
+    parts(a).cmp(&parts(b)).then_with(|| a.cmp(b))
+}
+
+/// Looks up the file for the current game version of every addon with an id.
+/// Addons Addown installed are compared by file id, others by their toc or changelog version.
+async fn check_latest_versions(
+    addons: &mut [InstalledAddon],
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = Manifest::load()?;
+    for addon in addons.iter_mut().filter(|addon| addon.id != 0) {
+        cancel.check()?;
+        let latest = match cancel.run(latest_file(addon)).await {
+            Ok(Some(latest)) => latest,
+            Ok(None) => continue,
+            Err(e) => {
+                cancel.check()?;
+                eprintln!("Could not look up {} ({}): {}", addon.name, addon.id, e);
+                continue;
+            }
+        };
+
+        addon.outdated = match manifest.find(addon.id) {
+            Some(entry) if latest.id != 0 => Some(entry.file_id != latest.id),
+            Some(entry) => Some(entry.display_name != latest.display_name),
+            None if addon.version.is_empty() => None,
+            None => Some(
+                !latest.display_name.contains(&addon.version)
+                    && !latest.file_name.contains(&addon.version),
+            ),
+        };
+        addon.latest_version = Some(latest.display_name.clone());
+    }
+
+    Ok(())
+}
+
+/// The file for the current game version of an addon, `None` when its provider has none
+async fn latest_file(
+    addon: &InstalledAddon,
+) -> Result<Option<AddonFile>, Box<dyn std::error::Error>> {
+    if !providers::has_remote(&addon.provider) {
+        return Ok(None);
+    }
+    if addon.provider != DEFAULT_PROVIDER {
+        let provider = providers::provider(&addon.provider)?;
+        return Ok(Some(provider.resolve_download(&addon.source_id).await?));
+    }
+
+    let game_mod = curseforge_api::get_mod_info(addon.id).await?;
+    Ok(game_version::get_mod_file_for_game_version(&game_mod)
+        .ok()
+        .map(curseforge::addon_file))
+}
+
+/// Reads the tocs of every addon in AddOns, looking up CurseForge ids for addons without one
+pub async fn scan_installed_mods() -> Result<Vec<InstalledAddon>, Box<dyn std::error::Error>> {
     let config = CurseForgeConfig::get();
     let addon_path = config.wow_path.to_owned() + config.path_suffix.as_str();
     let re = Regex::new(r"\|c[fF].{7}").unwrap();
 
-    let mut installed_mods: Vec<ModRow> = Vec::new();
-    println!("{}", addon_path);
+    let manifest = Manifest::load()?;
+    let mut installed_mods: Vec<InstalledAddon> = Vec::new();
     // list directories in addon_path
     let entries = std::fs::read_dir(addon_path)?;
     for entry in entries {
@@ -26,7 +216,8 @@ pub async fn get_installed_mods() -> Result<(), Box<dyn std::error::Error>> {
                         == entry.file_name().into_string().unwrap() + "_Mainline.toc"
                 {
                     let mut project_id = 0;
-                    let download_count = 0;
+                    let mut wowi_id = String::new();
+                    let mut wago_id = String::new();
                     let mut title = String::new();
                     let mut toc_version = String::new();
                     let mut changelog_version = String::new();
@@ -55,6 +246,12 @@ pub async fn get_installed_mods() -> Result<(), Box<dyn std::error::Error>> {
                             }
                             //println!("    - Curse Project ID: {}", project_id);
                         }
+                        if line.starts_with("## X-WoWI-ID:") {
+                            wowi_id = line.replace("## X-WoWI-ID:", "").trim().to_string();
+                        }
+                        if line.starts_with("## X-Wago-ID:") {
+                            wago_id = line.replace("## X-Wago-ID:", "").trim().to_string();
+                        }
                         if line.starts_with("## Title:") {
                             title = line.replace("## Title:", "").trim().to_string();
                             title = title.replace("[", "").replace("|r", "").replace("]", "");
@@ -71,17 +268,12 @@ pub async fn get_installed_mods() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
 
-                    if dependency_dir && project_id == 0 {
+                    if dependency_dir && project_id == 0 && wowi_id.is_empty() && wago_id.is_empty()
+                    {
                         //println!("Filtering out directory: {}", file.path().to_string_lossy());
                         continue;
                     }
-                    let already_added_mod = installed_mods.iter().any(|row| {
-                        if let ModRow::Data { name, .. } = row {
-                            *name == title
-                        } else {
-                            false
-                        }
-                    });
+                    let already_added_mod = installed_mods.iter().any(|addon| addon.name == title);
 
                     if already_added_mod {
                         //println!("Skipping duplicate mod: {}", title);
@@ -100,33 +292,159 @@ pub async fn get_installed_mods() -> Result<(), Box<dyn std::error::Error>> {
                             get_changelog_lua_version(file.path().to_string_lossy().to_string());
                     }
 
-                    if project_id == 0 {
-                        project_id = get_id_by_search(&title).await;
-                    }
+                    let folder = entry.file_name().to_string_lossy().to_string();
+                    let installed_entry = manifest
+                        .addons
+                        .iter()
+                        .find(|installed| installed.directories.contains(&folder));
+                    // what Addown installed is known, otherwise the toc ids are trusted, with
+                    // other providers only used when there is no CurseForge id
+                    let toc_source = [(wago::NAME, wago_id), (wowinterface::NAME, wowi_id)]
+                        .into_iter()
+                        .find(|(_, id)| !id.is_empty());
+                    let (provider, source_id) = match (installed_entry, toc_source) {
+                        (Some(installed), _) => {
+                            project_id = installed.mod_id;
+                            (installed.provider.clone(), installed.provider_id())
+                        }
+                        (None, Some((name, id))) if project_id == 0 => {
+                            project_id = providers::provider(name)?.local_id(&id)?;
+                            (name.to_string(), id)
+                        }
+                        _ => {
+                            if project_id == 0 {
+                                // an addon that can't be looked up, e.g. offline, stays unknown
+                                project_id = match get_id_by_search(&title).await {
+                                    Ok(id) => id,
+                                    Err(e) => {
+                                        eprintln!("Could not look up {}: {}", title, e);
+                                        0
+                                    }
+                                };
+                            }
+                            (DEFAULT_PROVIDER.to_string(), project_id.to_string())
+                        }
+                    };
 
                     let version = if !changelog_version.is_empty() {
                         changelog_version
                     } else {
                         toc_version
                     };
-                    let mod_row =
-                        ModRow::new_data(project_id, title, version, notes, download_count);
-                    installed_mods.push(mod_row);
+                    let updated = match installed_entry {
+                        Some(installed) => Some(installed.installed_at),
+                        None => file
+                            .metadata()?
+                            .modified()?
+                            .duration_since(UNIX_EPOCH)
+                            .ok()
+                            .map(|since| since.as_secs()),
+                    };
+                    installed_mods.push(InstalledAddon {
+                        id: project_id,
+                        provider,
+                        source_id,
+                        name: title,
+                        version,
+                        notes,
+                        size_bytes: dir_size(&entry.path())?,
+                        updated,
+                        latest_version: None,
+                        outdated: None,
+                        linked_to: dev::is_link(&entry.path())
+                            .then(|| fs::read_link(entry.path()).ok())
+                            .flatten()
+                            .map(|target| target.display().to_string()),
+                        folder,
+                    });
                 }
             }
         }
     }
 
-    let mut mod_table = ModTable::new();
-    mod_table.populate_installed_mods_table(installed_mods)?;
-    mod_table.print_table();
-    mod_table.print_table_view();
+    installed_mods.sort_by_key(|addon| addon.name.clone());
+    Ok(installed_mods)
+}
 
-    Ok(())
+/// Names of the folders in AddOns that WoW loads as addons, libraries included, sorted
+pub fn installed_folders() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(CurseForgeConfig::get().addons_path())? {
+        let entry = entry?;
+        let folder = entry.file_name().to_string_lossy().to_string();
+        let has_toc = [
+            format!("{}.toc", folder),
+            format!("{}_Mainline.toc", folder),
+        ]
+        .iter()
+        .any(|toc| entry.path().join(toc).is_file());
+        if has_toc {
+            folders.push(folder);
+        }
+    }
+    folders.sort_by_key(|folder| folder.to_lowercase());
+
+    Ok(folders)
+}
+
+/// Total size of the files under a folder
+fn dir_size(path: &Path) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Reads the `## Version:` of the toc matching an addon folder's name, if it has one
+pub fn read_toc_version(addon_dir: &Path) -> Option<String> {
+    read_toc_field(addon_dir, "Version")
+}
+
+/// Reads a `## <field>:` line of the toc matching an addon folder's name
+pub fn read_toc_field(addon_dir: &Path, field: &str) -> Option<String> {
+    let folder = addon_dir.file_name()?.to_string_lossy().to_string();
+    let prefix = format!("## {}:", field);
+    [
+        format!("{}.toc", folder),
+        format!("{}_Mainline.toc", folder),
+    ]
+    .iter()
+    .filter_map(|toc| fs::read_to_string(addon_dir.join(toc)).ok())
+    .flat_map(|content| {
+        content
+            .lines()
+            .find(|line| line.starts_with(&prefix))
+            .map(|line| line.replace(&prefix, "").trim().to_string())
+    })
+    .next()
+}
+
+/// Reads the newest version from the changelog in an addon folder, if it has one
+pub fn read_changelog_version(addon_dir: &Path) -> Option<String> {
+    ["CHANGELOG.md", "CHANGELOG.txt", "Changelog.lua"]
+        .iter()
+        .filter(|file_name| addon_dir.join(file_name).is_file())
+        .map(|file_name| {
+            let path = addon_dir.join(file_name).to_string_lossy().to_string();
+            match *file_name {
+                "CHANGELOG.md" => get_changelog_md_version(path),
+                "CHANGELOG.txt" => get_changelog_txt_version(path),
+                _ => get_changelog_lua_version(path),
+            }
+        })
+        .find(|version| !version.is_empty())
 }
 
 fn get_changelog_md_version(path: String) -> String {
-    let content = fs::read_to_string(path).unwrap();
+    let content = fs::read_to_string(path).unwrap_or_default();
     let lines = content.lines();
     for line in lines {
         if line.starts_with("## [") {
@@ -135,7 +453,7 @@ fn get_changelog_md_version(path: String) -> String {
                 .nth(1)
                 .unwrap()
                 .split("]")
-                .nth(0)
+                .next()
                 .unwrap()
                 .to_string();
         }
@@ -144,18 +462,18 @@ fn get_changelog_md_version(path: String) -> String {
 }
 
 fn get_changelog_txt_version(path: String) -> String {
-    let content = fs::read_to_string(path).unwrap();
+    let content = fs::read_to_string(path).unwrap_or_default();
     let lines = content.lines();
     for line in lines {
         if line.starts_with("##") {
-            return line.split(" ").nth(1).unwrap().to_string();
+            return line.split(" ").nth(1).unwrap_or_default().to_string();
         }
     }
     String::new()
 }
 
 fn get_changelog_lua_version(path: String) -> String {
-    let content = fs::read_to_string(path).unwrap();
+    let content = fs::read_to_string(path).unwrap_or_default();
     let lines = content.lines();
     for line in lines {
         if line.starts_with("v.") {
@@ -165,13 +483,35 @@ fn get_changelog_lua_version(path: String) -> String {
     String::new()
 }
 
-async fn get_id_by_search(mod_name: &String) -> u32 {
-    let mods = curseforge_api::search_mods(1, &mod_name).await.unwrap();
+/// The CurseForge id of the mod named exactly `mod_name`, or 0 when there is none
+async fn get_id_by_search(mod_name: &str) -> Result<u32, Box<dyn std::error::Error>> {
+    let mods = curseforge_api::search_mods(WOW_GAME_ID, mod_name).await?;
     for game_mod in mods {
         if game_mod.name.to_lowercase() == mod_name.to_lowercase() {
             //println!("Found mod: {}, ({})", game_mod.name, game_mod.id);
-            return game_mod.id;
+            return Ok(game_mod.id);
         }
     }
-    0
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn compare_versions_orders_numbers_numerically() {
+        assert_eq!(compare_versions("10.0.2", "9.2.0"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.9", "v1.10"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0-beta"), Ordering::Less);
+        assert_eq!(compare_versions("2.0", "2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("", "1.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn compare_versions_falls_back_to_string_order() {
+        assert_eq!(compare_versions("1.01", "1.1"), Ordering::Less);
+        assert_eq!(compare_versions("alpha", "beta"), Ordering::Less);
+    }
 }
@@ -1,11 +1,33 @@
+pub mod addons_txt;
+pub mod backup;
+pub mod cancellation;
 pub mod cli_loop;
+pub mod completion;
 pub mod config;
 pub mod curseforge_api;
+pub mod download_cache;
+pub mod fingerprint;
 pub mod game_version;
+pub mod groups;
+pub mod history;
+pub mod html;
 pub mod installed_mods;
+pub mod manifest;
+pub mod migrate;
 pub mod mod_table;
 pub mod models;
+pub mod output;
+pub mod profile;
+pub mod providers;
+pub mod snapshot;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod updater;
+pub mod wtf;
 
+pub use cancellation::{CancellationToken, Interrupted};
 pub use config::CurseForgeConfig;
 pub use curseforge_api::*;
 pub use game_version::*;
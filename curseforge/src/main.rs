@@ -1,18 +1,28 @@
+use clap::Parser;
 use curseforge::cli_loop::{
-    crate_rustyline_background_loop, create_ctrlc_background_loop, main_loop, CtrlCState,
-    InputEvent,
+    crate_rustyline_background_loop, create_ctrlc_background_loop, main_loop, run_single_command,
+    Addown, CtrlCState, InputEvent,
 };
+use curseforge::CurseForgeConfig;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-mod config;
-mod curseforge_api;
-mod installed_mods;
-mod mod_table;
-mod models;
-
 #[tokio::main]
 async fn main() {
+    // a subcommand on the shell command line runs once and exits, e.g. `addown update --all`
+    let cli = Addown::parse();
+    if let Some(command) = cli.command {
+        let exit_code = run_single_command(command, cli.output).await;
+        std::process::exit(exit_code);
+    }
+
+    // the rustyline thread reads the history settings, so a missing API key is reported here
+    // before the thread could panic on it
+    if let Err(e) = CurseForgeConfig::try_get() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
     println!("Welcome to Kubellm Interactive CLI!");
     println!("Type 'help' for available commands or 'exit' to quit.");
     println!("Press Ctrl+C twice quickly to force exit.\n");
@@ -23,10 +33,10 @@ async fn main() {
     // Channel for communication between rustyline and main async task
     let (input_tx, mut input_rx) = tokio::sync::mpsc::unbounded_channel::<InputEvent>();
 
-    // Spawn rustyline in a blocking thread (always listening)
-    let input_tx_clone = input_tx.clone();
+    // Spawn rustyline in a blocking thread (always listening). It owns the only sender, so the
+    // main loop sees the channel close if the thread ends.
     let rusty_ctrl_c_state_clone = ctrl_c_state.clone();
-    crate_rustyline_background_loop(ctrl_c_timeout, input_tx_clone, rusty_ctrl_c_state_clone);
+    crate_rustyline_background_loop(ctrl_c_timeout, input_tx, rusty_ctrl_c_state_clone);
 
     // Background task to clear Ctrl+C timeout messages
     let ctrl_c_state_clone = ctrl_c_state.clone();
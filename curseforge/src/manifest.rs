@@ -0,0 +1,128 @@
+use crate::config::CurseForgeConfig;
+use crate::providers::DEFAULT_PROVIDER;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An addon installed by addown and the file it was installed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub mod_id: u32,
+    pub name: String,
+    pub file_id: u32,
+    pub file_name: String,
+    pub display_name: String,
+    /// Top level folders the addon's archive extracted into AddOns
+    pub directories: Vec<String>,
+    /// Unix timestamp of the install
+    pub installed_at: u64,
+    /// Name of the provider the addon was installed from
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    /// Id of the addon at its provider, empty for CurseForge entries written before
+    /// providers were recorded
+    #[serde(default)]
+    pub source_id: String,
+    /// Release tag the files came from, for providers that publish releases by tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_tag: Option<String>,
+    /// Ids of the releases a dev link replaced, restored when it is unlinked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<u32>,
+}
+
+impl ManifestEntry {
+    /// Id to look the addon up with at its provider
+    pub fn provider_id(&self) -> String {
+        if self.source_id.is_empty() {
+            self.mod_id.to_string()
+        } else {
+            self.source_id.clone()
+        }
+    }
+}
+
+/// Entries written before providers were recorded all came from CurseForge
+fn default_provider() -> String {
+    DEFAULT_PROVIDER.to_string()
+}
+
+/// Record of the addons addown has installed, stored as json in the data dir
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub addons: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn path() -> String {
+        format!("{}/manifest.json", CurseForgeConfig::get().data_dir)
+    }
+
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the manifest to a temporary file first and renames it into place, so a failed
+    /// write never leaves a truncated manifest behind
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&CurseForgeConfig::get().data_dir)?;
+        let path = Self::path();
+        let temp_path = format!("{}.tmp", path);
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        if let Err(e) = fs::rename(&temp_path, &path) {
+            fs::remove_file(&temp_path).ok();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    pub fn find(&self, mod_id: u32) -> Option<&ManifestEntry> {
+        self.addons.iter().find(|entry| entry.mod_id == mod_id)
+    }
+
+    /// Adds an entry, replacing any existing entry for the same mod
+    pub fn upsert(&mut self, entry: ManifestEntry) {
+        self.remove(entry.mod_id);
+        self.addons.push(entry);
+        self.addons.sort_by_key(|entry| entry.name.to_lowercase());
+    }
+
+    pub fn remove(&mut self, mod_id: u32) -> Option<ManifestEntry> {
+        let index = self
+            .addons
+            .iter()
+            .position(|entry| entry.mod_id == mod_id)?;
+        Some(self.addons.remove(index))
+    }
+}
+
+/// Current time as a unix timestamp
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats a unix timestamp as a local date and time for display
+pub fn format_timestamp(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Formats a date from the CurseForge api, e.g. `2024-08-13T18:03:21.12Z`, like `format_timestamp`
+pub fn format_api_date(date: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(date)
+        .map(|time| format_timestamp(time.timestamp() as u64))
+        .unwrap_or_else(|_| date.to_string())
+}
@@ -0,0 +1,467 @@
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::curseforge_api;
+use crate::fingerprint;
+use crate::installed_mods::{read_toc_field, read_toc_version};
+use crate::manifest::{now_timestamp, Manifest, ManifestEntry};
+use crate::models::FingerprintMatch;
+use crate::providers::{self, dev, github, wago, wowinterface, DEFAULT_PROVIDER};
+use base64::Engine;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Addon managers whose state `import --from` can take over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Manager {
+    /// The CurseForge app's addon cache json
+    Curseforge,
+    /// A WowUp export string, or a file holding one
+    Wowup,
+    /// Ajour's addons.yml
+    Ajour,
+}
+
+impl Manager {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Curseforge => "the CurseForge app",
+            Self::Wowup => "WowUp",
+            Self::Ajour => "Ajour",
+        }
+    }
+}
+
+/// An addon as another manager recorded it
+#[derive(Debug, Clone)]
+pub struct ForeignAddon {
+    pub provider: &'static str,
+    /// Id of the addon at its provider
+    pub id: String,
+    pub name: String,
+    /// File id the manager installed, 0 when it doesn't know
+    pub file_id: u32,
+    pub version: String,
+    /// AddOns folders of the addon, empty when the manager doesn't record them
+    pub folders: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurseForgeAppAddon {
+    #[serde(rename = "addonID", alias = "addonId", alias = "id")]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "installedFile", default)]
+    installed_file: Option<CurseForgeAppFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurseForgeAppFile {
+    id: u32,
+    #[serde(rename = "displayName", default)]
+    display_name: String,
+    #[serde(default)]
+    modules: Vec<CurseForgeAppModule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurseForgeAppModule {
+    #[serde(rename = "foldername", alias = "folderName", alias = "name")]
+    folder_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WowUpAddon {
+    id: serde_json::Value,
+    name: String,
+    #[serde(rename = "providerName")]
+    provider_name: String,
+    #[serde(rename = "installedVersion", alias = "version", default)]
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AjourAddon {
+    title: String,
+    /// `Curse`, `WowI`, or `Git: Github` and the like
+    #[serde(default)]
+    repository: serde_yaml::Value,
+    #[serde(default)]
+    repository_id: serde_yaml::Value,
+    #[serde(default)]
+    folder_names: Vec<String>,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// Reads the addons another manager installed. For WowUp `source` may be the export string
+/// itself instead of a file.
+pub fn read_state(
+    manager: Manager,
+    source: &str,
+) -> Result<Vec<ForeignAddon>, Box<dyn std::error::Error>> {
+    let content = match fs::read_to_string(source) {
+        Ok(content) => content,
+        Err(_) if manager == Manager::Wowup && !Path::new(source).exists() => source.to_string(),
+        Err(e) => return Err(format!("Could not read {}: {}", source, e).into()),
+    };
+    let addons = match manager {
+        Manager::Curseforge => read_curseforge_app(&content)?,
+        Manager::Wowup => read_wowup(&content)?,
+        Manager::Ajour => read_ajour(&content)?,
+    };
+    Ok(addons)
+}
+
+fn read_curseforge_app(content: &str) -> Result<Vec<ForeignAddon>, Box<dyn std::error::Error>> {
+    let mut value: serde_json::Value = serde_json::from_str(content)?;
+    if !value.is_array() {
+        value = ["installedAddons", "InstalledAddons", "addons"]
+            .iter()
+            .find_map(|key| value.get_mut(*key).map(serde_json::Value::take))
+            .ok_or("No installed addons found in the CurseForge app's cache")?;
+    }
+    let addons: Vec<CurseForgeAppAddon> = serde_json::from_value(value)?;
+
+    Ok(addons
+        .into_iter()
+        .map(|addon| {
+            let file = addon.installed_file;
+            ForeignAddon {
+                provider: DEFAULT_PROVIDER,
+                id: addon.id.to_string(),
+                name: addon.name,
+                file_id: file.as_ref().map(|file| file.id).unwrap_or(0),
+                version: file
+                    .as_ref()
+                    .map(|file| file.display_name.clone())
+                    .unwrap_or_default(),
+                folders: file
+                    .map(|file| {
+                        file.modules
+                            .into_iter()
+                            .map(|module| module.folder_name)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+/// WowUp exports a base64 encoded json list of addons
+fn read_wowup(content: &str) -> Result<Vec<ForeignAddon>, Box<dyn std::error::Error>> {
+    let content = content.trim();
+    let json = if content.starts_with(['{', '[']) {
+        content.to_string()
+    } else {
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(content)
+            .map_err(|_| "Not a WowUp export string")?;
+        String::from_utf8(decoded)?
+    };
+    let mut value: serde_json::Value = serde_json::from_str(&json)?;
+    if let Some(addons) = value.get_mut("addons") {
+        value = addons.take();
+    }
+    let addons: Vec<WowUpAddon> = serde_json::from_value(value)?;
+
+    let mut foreign = Vec::new();
+    for addon in addons {
+        let Some(provider) = provider_name(&addon.provider_name) else {
+            eprintln!(
+                "Leaving out {}, Addown can't install from {}",
+                addon.name, addon.provider_name
+            );
+            continue;
+        };
+        let id = match addon.id {
+            serde_json::Value::String(id) => id,
+            id => id.to_string(),
+        };
+        foreign.push(ForeignAddon {
+            provider,
+            id,
+            name: addon.name,
+            file_id: 0,
+            version: addon.version.unwrap_or_default(),
+            folders: Vec::new(),
+        });
+    }
+    Ok(foreign)
+}
+
+/// Ajour keeps the addons of each flavor under its name
+fn read_ajour(content: &str) -> Result<Vec<ForeignAddon>, Box<dyn std::error::Error>> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(content)?;
+    if let serde_yaml::Value::Mapping(flavors) = &mut value {
+        value = ["retail", "Retail"]
+            .iter()
+            .find_map(|key| flavors.remove(*key))
+            .ok_or("No retail addons found in Ajour's addons.yml")?;
+    }
+    let addons: Vec<AjourAddon> = serde_yaml::from_value(value)?;
+
+    let mut foreign = Vec::new();
+    for addon in addons {
+        // git repositories are recorded as `Git: Github`
+        let repository = match &addon.repository {
+            serde_yaml::Value::String(name) => name.clone(),
+            serde_yaml::Value::Mapping(kind) => kind
+                .values()
+                .next()
+                .and_then(serde_yaml::Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            _ => String::new(),
+        };
+        let id = match &addon.repository_id {
+            serde_yaml::Value::String(id) => id.clone(),
+            serde_yaml::Value::Number(id) => id.to_string(),
+            _ => String::new(),
+        };
+        let Some(provider) = provider_name(&repository).filter(|_| !id.is_empty()) else {
+            eprintln!(
+                "Leaving out {}, Addown can't install from {}",
+                addon.title,
+                if repository.is_empty() {
+                    "an unknown source"
+                } else {
+                    &repository
+                }
+            );
+            continue;
+        };
+        foreign.push(ForeignAddon {
+            provider,
+            id,
+            name: addon.title,
+            file_id: 0,
+            version: addon.version.unwrap_or_default(),
+            folders: addon.folder_names,
+        });
+    }
+    Ok(foreign)
+}
+
+/// Addown's name for the providers other managers know
+fn provider_name(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "curse" | "curseforge" => Some(DEFAULT_PROVIDER),
+        "wowi" | "wowinterface" => Some(wowinterface::NAME),
+        "wago" | "wagoaddons" => Some(wago::NAME),
+        "git" | "github" => Some(github::NAME),
+        _ => None,
+    }
+}
+
+/// Records the addons another manager installed in the manifest without reinstalling them.
+/// CurseForge addons are only taken over when the fingerprints of their folders match one
+/// of the addon's files, so the manifest knows which file is really installed. Folders that
+/// are dev links or that Addown already tracks are left alone.
+pub async fn adopt(
+    manager: Manager,
+    addons: &[ForeignAddon],
+    cancel: &CancellationToken,
+) -> Result<Vec<ManifestEntry>, Box<dyn std::error::Error>> {
+    let addons_path = CurseForgeConfig::get().addons_path();
+    let mut manifest = Manifest::load()?;
+
+    let mut untracked = Vec::new();
+    for entry in fs::read_dir(&addons_path)? {
+        let entry = entry?;
+        let folder = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir()
+            && !dev::is_link(&entry.path())
+            && !manifest
+                .addons
+                .iter()
+                .any(|installed| installed.directories.contains(&folder))
+        {
+            untracked.push(folder);
+        }
+    }
+
+    // CurseForge addons are left out when their fingerprints can't be looked up, the other
+    // providers' addons don't need them
+    let matches = if addons
+        .iter()
+        .any(|addon| addon.provider == DEFAULT_PROVIDER)
+    {
+        eprintln!("Fingerprinting {} addon folders...", untracked.len());
+        match fingerprint_matches(&addons_path, &untracked, cancel).await {
+            Ok(matches) => Some(matches),
+            Err(e) => {
+                cancel.check()?;
+                eprintln!(
+                    "Could not look up fingerprints, leaving out CurseForge addons: {}",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut adopted = Vec::new();
+    for addon in addons {
+        cancel.check()?;
+        let provider = providers::provider(addon.provider)?;
+        let mod_id = provider.local_id(&addon.id)?;
+        if manifest.find(mod_id).is_some() {
+            eprintln!("Skipping {}, Addown already manages it", addon.name);
+            continue;
+        }
+
+        let entry = if addon.provider == DEFAULT_PROVIDER {
+            let Some(matches) = &matches else {
+                continue;
+            };
+            let Some((file_match, folders)) = matches.get(&mod_id) else {
+                eprintln!(
+                    "Leaving out {}, its folders don't match any of its CurseForge files",
+                    addon.name
+                );
+                continue;
+            };
+            if addon.file_id != 0 && addon.file_id != file_match.file.id {
+                eprintln!(
+                    "{} has {} installed, not the file {} recorded",
+                    addon.name,
+                    file_match.file.display_name,
+                    manager.label()
+                );
+            }
+            ManifestEntry {
+                mod_id,
+                name: addon.name.clone(),
+                file_id: file_match.file.id,
+                file_name: file_match.file.file_name.clone(),
+                display_name: file_match.file.display_name.clone(),
+                directories: folders.clone(),
+                installed_at: now_timestamp(),
+                provider: DEFAULT_PROVIDER.to_string(),
+                source_id: addon.id.clone(),
+                release_tag: None,
+                replaced: Vec::new(),
+            }
+        } else {
+            let folders = addon_folders(&addons_path, addon, &untracked);
+            if folders.is_empty() {
+                eprintln!("Leaving out {}, its folders aren't in AddOns", addon.name);
+                continue;
+            }
+            let version = if addon.version.is_empty() {
+                read_toc_version(&Path::new(&addons_path).join(&folders[0])).unwrap_or_default()
+            } else {
+                addon.version.clone()
+            };
+            ManifestEntry {
+                mod_id,
+                name: addon.name.clone(),
+                file_id: addon.file_id,
+                file_name: String::new(),
+                display_name: version.clone(),
+                directories: folders,
+                installed_at: now_timestamp(),
+                provider: addon.provider.to_string(),
+                source_id: addon.id.clone(),
+                // other managers record GitHub releases by tag
+                release_tag: (addon.provider == github::NAME && !version.is_empty())
+                    .then_some(version),
+                replaced: Vec::new(),
+            }
+        };
+        untracked.retain(|folder| !entry.directories.contains(folder));
+        manifest.upsert(entry.clone());
+        adopted.push(entry);
+    }
+    manifest.save()?;
+
+    Ok(adopted)
+}
+
+/// Matches the fingerprints of the folders to CurseForge files. Returns the matched file of
+/// each mod and the folders whose fingerprints matched its modules.
+async fn fingerprint_matches(
+    addons_path: &str,
+    folders: &[String],
+    cancel: &CancellationToken,
+) -> Result<HashMap<u32, (FingerprintMatch, Vec<String>)>, Box<dyn std::error::Error>> {
+    let mut fingerprints = HashMap::new();
+    for folder in folders {
+        cancel.check()?;
+        match fingerprint::folder_fingerprint(&Path::new(addons_path).join(folder)) {
+            Ok(fingerprint) => {
+                fingerprints.insert(fingerprint, folder.clone());
+            }
+            Err(e) => eprintln!("Could not fingerprint {}: {}", folder, e),
+        }
+    }
+    if fingerprints.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let keys: Vec<u32> = fingerprints.keys().copied().collect();
+    let mut matches = HashMap::new();
+    for file_match in cancel
+        .run(curseforge_api::get_fingerprint_matches(&keys))
+        .await?
+    {
+        let folders: Vec<String> = file_match
+            .file
+            .modules
+            .iter()
+            .filter_map(|module| fingerprints.get(&(module.fingerprint as u32)))
+            .cloned()
+            .collect();
+        if !folders.is_empty() {
+            matches.insert(file_match.id, (file_match, folders));
+        }
+    }
+    Ok(matches)
+}
+
+/// Folders of an addon from a provider without fingerprints: the ones the manager recorded,
+/// otherwise those whose toc has the addon's id or whose name is the addon's
+fn addon_folders(addons_path: &str, addon: &ForeignAddon, untracked: &[String]) -> Vec<String> {
+    if !addon.folders.is_empty() {
+        return addon
+            .folders
+            .iter()
+            .filter(|folder| untracked.contains(folder))
+            .cloned()
+            .collect();
+    }
+
+    let toc_field = match addon.provider {
+        wowinterface::NAME => Some("X-WoWI-ID"),
+        wago::NAME => Some("X-Wago-ID"),
+        _ => None,
+    };
+    let by_toc: Vec<String> = untracked
+        .iter()
+        .filter(|folder| {
+            toc_field.is_some_and(|field| {
+                read_toc_field(&Path::new(addons_path).join(folder), field)
+                    .is_some_and(|id| id == addon.id)
+            })
+        })
+        .cloned()
+        .collect();
+    if !by_toc.is_empty() {
+        return by_toc;
+    }
+    let repo_name = addon.id.rsplit('/').next().unwrap_or_default();
+    untracked
+        .iter()
+        .filter(|folder| {
+            folder.eq_ignore_ascii_case(&addon.name) || folder.eq_ignore_ascii_case(repo_name)
+        })
+        .cloned()
+        .collect()
+}
@@ -1,214 +1,443 @@
-use crate::models::Mod;
-use std::str::SplitWhitespace;
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::download_cache::format_size;
+use crate::installed_mods::InstalledAddon;
+use crate::manifest::format_timestamp;
+use crate::providers::AddonInfo;
+use std::io;
+use std::io::Write;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+/// Number of mods shown on each page of a table view
 const TABLE_VIEW_ROWS: usize = 20;
+/// Width used when the terminal size can't be detected, e.g. when output is piped
+const DEFAULT_TERMINAL_WIDTH: usize = 160;
+
+/// A column that can be shown in a mod table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Name,
+    Version,
+    Summary,
+    Downloads,
+    Folder,
+    Size,
+    Updated,
+    /// Newest version for the current game version, only known once it has been looked up
+    Latest,
+}
+
+impl Column {
+    /// Columns shown for search results unless the config picks others
+    pub const SEARCH: [Column; 5] = [
+        Column::Id,
+        Column::Name,
+        Column::Version,
+        Column::Summary,
+        Column::Downloads,
+    ];
+
+    /// Columns shown for installed addons unless the config picks others
+    pub const INSTALLED: [Column; 8] = [
+        Column::Id,
+        Column::Name,
+        Column::Folder,
+        Column::Version,
+        Column::Latest,
+        Column::Updated,
+        Column::Size,
+        Column::Summary,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "id" => Some(Column::Id),
+            "name" => Some(Column::Name),
+            "version" => Some(Column::Version),
+            "summary" => Some(Column::Summary),
+            "downloads" | "download_count" => Some(Column::Downloads),
+            "folder" => Some(Column::Folder),
+            "size" => Some(Column::Size),
+            "updated" => Some(Column::Updated),
+            "latest" => Some(Column::Latest),
+            _ => None,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Id => "Mod ID",
+            Column::Name => "Name",
+            Column::Version => "Version/Display Name",
+            Column::Summary => "Summary",
+            Column::Downloads => "Download Count",
+            Column::Folder => "Folder",
+            Column::Size => "Size",
+            Column::Updated => "Updated",
+            Column::Latest => "Latest",
+        }
+    }
+
+    /// Smallest and largest width the column is given
+    fn width_range(&self) -> (usize, usize) {
+        match self {
+            Column::Id => (6, 12),
+            Column::Name => (12, 40),
+            Column::Version => (10, 30),
+            Column::Summary => (12, 60),
+            Column::Downloads => (9, 14),
+            Column::Folder => (8, 30),
+            Column::Size => (8, 10),
+            Column::Updated => (16, 16),
+            Column::Latest => (10, 30),
+        }
+    }
+
+    /// Whether the column gives up width when the terminal is too narrow
+    fn shrinks(&self) -> bool {
+        matches!(
+            self,
+            Column::Name | Column::Version | Column::Summary | Column::Folder | Column::Latest
+        )
+    }
+}
+
+/// What to do with cell text that is wider than its column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wrap onto extra lines at word boundaries
+    Wrap,
+    /// Cut the text and end it with an ellipsis
+    Truncate,
+}
 
 pub enum ModRow {
-    Header {
-        id: String,
-        name: String,
-        version: String,
-        summary: String,
-        download_count: String,
-    },
+    /// The column titles
+    Header,
     Data {
-        id: u32,
+        id: String,
         name: String,
         version: String,
         summary: String,
-        download_count: u32,
+        download_count: u64,
+        folder: String,
+        size: Option<u64>,
+        updated: Option<u64>,
+        latest_version: Option<String>,
     },
 }
 
 impl ModRow {
-    pub fn new_header(
+    pub fn new_data(
         id: impl Into<String>,
         name: impl Into<String>,
         version: impl Into<String>,
         summary: impl Into<String>,
-        download_count: impl Into<String>,
+        download_count: u64,
     ) -> Self {
-        Self::Header {
+        Self::Data {
             id: id.into(),
             name: name.into(),
             version: version.into(),
             summary: summary.into(),
-            download_count: download_count.into(),
+            download_count,
+            folder: String::new(),
+            size: None,
+            updated: None,
+            latest_version: None,
         }
     }
 
-    pub fn new_data(
-        id: u32,
-        name: impl Into<String>,
-        version: impl Into<String>,
-        summary: impl Into<String>,
-        download_count: u32,
-    ) -> Self {
+    /// A row for an addon found in the AddOns folder
+    pub fn new_installed(addon: &InstalledAddon) -> Self {
         Self::Data {
-            id,
-            name: name.into(),
-            version: version.into(),
-            summary: summary.into(),
-            download_count,
+            id: addon.id.to_string(),
+            name: addon.name.clone(),
+            version: match addon.linked_to {
+                Some(_) => format!("{} (dev)", addon.version).trim().to_string(),
+                None => addon.version.clone(),
+            },
+            summary: addon.notes.clone(),
+            download_count: 0,
+            folder: match &addon.linked_to {
+                Some(target) => format!("{} -> {}", addon.folder, target),
+                None => addon.folder.clone(),
+            },
+            size: Some(addon.size_bytes),
+            updated: addon.updated,
+            latest_version: addon.latest_version.clone(),
         }
     }
 
-    pub fn format_row(&self) -> String {
+    /// The text shown for this row in a column
+    pub fn cell(&self, column: Column) -> String {
         match self {
-            ModRow::Header {
-                id,
-                name,
-                version,
-                summary,
-                download_count,
-            } => {
-                format!(
-                    "| {:<12} | {:<30} | {:<30} | {:<50} | {:<14} |",
-                    id, name, version, summary, download_count
-                )
-            }
+            ModRow::Header => column.title().to_string(),
             ModRow::Data {
                 id,
                 name,
                 version,
                 summary,
                 download_count,
-            } => {
-                let name_rows_words = name.split_whitespace();
-                let summary_rows_words = summary.split_whitespace();
-                let version_rows_words = version.split_whitespace();
-
-                let mut name_rows: Vec<String> = Vec::new();
-                let mut summary_rows: Vec<String> = Vec::new();
-                let mut version_rows: Vec<String> = Vec::new();
-                let mut line_rows: Vec<String> = Vec::new();
-
-                name_rows.push(String::new());
-                Self::wrap_field_line(name_rows_words, &mut name_rows, 30);
-
-                summary_rows.push(String::new());
-                Self::wrap_field_line(summary_rows_words, &mut summary_rows, 50);
-
-                version_rows.push(String::new());
-                Self::wrap_field_line(version_rows_words, &mut version_rows, 30);
-
-                let num_rows = std::cmp::max(
-                    std::cmp::max(name_rows.len(), summary_rows.len()),
-                    version_rows.len(),
-                );
-                for i in 0..num_rows {
-                    let name = if i < name_rows.len() {
-                        &name_rows[i]
-                    } else {
-                        ""
-                    };
-                    let summary = if i < summary_rows.len() {
-                        &summary_rows[i]
-                    } else {
-                        ""
-                    };
-                    let version = if i < version_rows.len() {
-                        &version_rows[i]
-                    } else {
-                        ""
-                    };
-                    if i == 0 {
-                        line_rows.push(format!(
-                            "| {:<12} | {:<30} | {:<30} | {:<50} | {:<14} |",
-                            id, name, version, summary, download_count
-                        ));
-                    } else {
-                        line_rows.push(format!(
-                            "| {:<12} | {:<30} | {:<30} | {:<50} | {:<14} |",
-                            "", name, version, summary, ""
-                        ));
-                    }
-                }
-                line_rows.join("\n")
-            }
+                folder,
+                size,
+                updated,
+                latest_version,
+            } => match column {
+                Column::Id => id.clone(),
+                Column::Name => name.clone(),
+                Column::Version => version.clone(),
+                Column::Summary => summary.clone(),
+                Column::Downloads => download_count.to_string(),
+                Column::Folder => folder.clone(),
+                Column::Size => size.map(format_size).unwrap_or_default(),
+                Column::Updated => updated.map(format_timestamp).unwrap_or_default(),
+                Column::Latest => latest_version.clone().unwrap_or_default(),
+            },
         }
     }
 
-    fn wrap_field_line(
-        version_rows_words: SplitWhitespace,
-        summary_rows: &mut Vec<String>,
-        length: usize,
-    ) {
-        for word in version_rows_words {
-            if summary_rows.last().unwrap().len() + word.len() + 1 > length {
-                summary_rows.push(word.to_string());
-            } else {
-                if !summary_rows.last().unwrap().is_empty() {
-                    summary_rows.last_mut().unwrap().push(' ');
+    pub fn format_row(&self, columns: &[(Column, usize)], overflow: Overflow) -> String {
+        let cell_lines: Vec<Vec<String>> = columns
+            .iter()
+            .map(|(column, width)| match (self, overflow) {
+                // headers are never wrapped so the table keeps a single header line
+                (ModRow::Header, _) | (_, Overflow::Truncate) => {
+                    vec![truncate(&self.cell(*column), *width)]
                 }
-                summary_rows.last_mut().unwrap().push_str(word);
+                (ModRow::Data { .. }, Overflow::Wrap) => wrap(&self.cell(*column), *width),
+            })
+            .collect();
+
+        let num_rows = cell_lines.iter().map(Vec::len).max().unwrap_or(1);
+        let mut line_rows: Vec<String> = Vec::new();
+        for i in 0..num_rows {
+            let cells: Vec<String> = columns
+                .iter()
+                .zip(&cell_lines)
+                .map(|((_, width), lines)| pad(lines.get(i).map_or("", String::as_str), *width))
+                .collect();
+            line_rows.push(format!("| {} |", cells.join(" | ")));
+        }
+        line_rows.join("\n")
+    }
+}
+
+/// Pads text with spaces to a display width
+fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Cuts text to fit a display width, ending it with an ellipsis when anything was cut
+fn truncate(text: &str, width: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.width() <= width {
+        return text;
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width + 1 > width {
+            break;
+        }
+        truncated.push(ch);
+        used += ch_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Wraps text at word boundaries to a display width, breaking words that don't fit on a line
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let current = lines.last().unwrap();
+        let needed = if current.is_empty() {
+            word.width()
+        } else {
+            current.width() + 1 + word.width()
+        };
+
+        if needed <= width {
+            let current = lines.last_mut().unwrap();
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+            continue;
+        }
+
+        if !lines.last().unwrap().is_empty() {
+            lines.push(String::new());
+        }
+        for ch in word.chars() {
+            let current = lines.last().unwrap();
+            // a character wider than the column gets a line of its own rather than none
+            if !current.is_empty() && current.width() + ch.width().unwrap_or(0) > width {
+                lines.push(String::new());
             }
+            lines.last_mut().unwrap().push(ch);
+        }
+    }
+
+    lines
+}
+
+/// A key typed while paging through a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageCommand {
+    Next,
+    Previous,
+    First,
+    Last,
+    /// A page number, counting from 1
+    Page(usize),
+    Quit,
+}
+
+impl PageCommand {
+    fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "" | "n" | "next" => Some(PageCommand::Next),
+            "p" | "prev" | "previous" => Some(PageCommand::Previous),
+            "f" | "first" => Some(PageCommand::First),
+            "l" | "last" => Some(PageCommand::Last),
+            "q" | "quit" => Some(PageCommand::Quit),
+            other => other.parse().ok().map(PageCommand::Page),
         }
     }
 }
 
+/// Number of pages `rows` entries take at `per_page` a page, an empty table still has one
+fn page_count(rows: usize, per_page: usize) -> usize {
+    rows.div_ceil(per_page).max(1)
+}
+
+/// The range of entries shown on a page, counting pages from 0
+fn page_bounds(page: usize, rows: usize, per_page: usize) -> (usize, usize) {
+    let start = std::cmp::min(page * per_page, rows);
+    (start, std::cmp::min(start + per_page, rows))
+}
+
+/// The page a command moves to from `page`, or the message to show when there is no such
+/// page. Moving past the first or last page stays there.
+fn turn_page(command: PageCommand, page: usize, page_count: usize) -> Result<usize, String> {
+    let last_page = page_count - 1;
+    match command {
+        PageCommand::Next => Ok(std::cmp::min(page + 1, last_page)),
+        PageCommand::Previous => Ok(page.saturating_sub(1)),
+        PageCommand::First => Ok(0),
+        PageCommand::Last => Ok(last_page),
+        PageCommand::Page(number) if (1..=page_count).contains(&number) => Ok(number - 1),
+        PageCommand::Page(number) => Err(format!(
+            "There is no page {}, pages go from 1 to {}",
+            number, page_count
+        )),
+        PageCommand::Quit => Ok(page),
+    }
+}
+
+/// The columns from the config, or `default` when it doesn't pick any
+fn configured_columns(default: &[Column]) -> Vec<Column> {
+    CurseForgeConfig::get()
+        .table_columns
+        .as_deref()
+        .map(|columns| columns.split(',').filter_map(Column::parse).collect())
+        .filter(|columns: &Vec<Column>| !columns.is_empty())
+        .unwrap_or_else(|| default.to_vec())
+}
+
+pub(crate) fn terminal_width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
 pub struct ModTable {
     rows: Vec<ModRow>,
+    /// The formatted header, repeated at the top of every page
+    formatted_header: String,
+    /// One formatted entry per mod, which can span several lines when cells wrap
     formatted_rows: Vec<String>,
+    separator: String,
     row_view_count: usize,
-    row_index: usize,
+    /// The page shown by `print_table_view`, counting from 0
+    page: usize,
+    columns: Vec<Column>,
+    overflow: Overflow,
+    width: usize,
+}
+
+impl Default for ModTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ModTable {
+    /// Creates a table using the columns and overflow mode from the config, sized to the terminal
     pub fn new() -> ModTable {
+        let config = CurseForgeConfig::get();
+        let overflow = match config.table_overflow.as_deref() {
+            Some("truncate") => Overflow::Truncate,
+            _ => Overflow::Wrap,
+        };
+
         Self {
             rows: Vec::new(),
+            formatted_header: String::new(),
             formatted_rows: Vec::new(),
+            separator: String::new(),
             row_view_count: TABLE_VIEW_ROWS,
-            row_index: 0
+            page: 0,
+            columns: configured_columns(&Column::SEARCH),
+            overflow,
+            width: terminal_width(),
         }
     }
 
     pub fn populate_mods_table(
         &mut self,
-        mods: Vec<Mod>,
+        addons: Vec<AddonInfo>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.add_row(ModRow::new_header(
-            "Mod ID",
-            "Name",
-            "Version/Display Name",
-            "Summary",
-            "Download Count",
-        ));
-
-        for mod_info in mods {
+        self.add_row(ModRow::Header);
+
+        for addon in addons {
             self.add_row(ModRow::new_data(
-                mod_info.id,
-                &mod_info.name,
-                &mod_info.latest_files[0].display_name,
-                &mod_info.summary,
-                mod_info.download_count,
+                addon.id,
+                addon.name,
+                addon.latest_version,
+                addon.summary,
+                addon.download_count,
             ));
         }
 
+        self.format_table();
+
         Ok(())
     }
 
+    /// Fills the table with installed addons, keeping their order
     pub fn populate_installed_mods_table(
         &mut self,
-        mods: Vec<ModRow>,
+        addons: &[InstalledAddon],
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.add_row(ModRow::new_header(
-            "Mod ID",
-            "Name",
-            "Version/Display Name",
-            "Summary",
-            "Download Count",
-        ));
-
-        let mut mods = mods;
-        mods.sort_by_key(|mod_row| match mod_row {
-            ModRow::Header { name, .. } => name.clone(),
-            ModRow::Data { name, .. } => name.clone(),
-        });
+        self.columns = configured_columns(&Column::INSTALLED);
+        // latest versions are only looked up for some views, leave the column out otherwise
+        if addons.iter().all(|addon| addon.latest_version.is_none()) {
+            self.columns.retain(|column| *column != Column::Latest);
+        }
 
-        for mod_row in mods {
-            self.add_row(mod_row);
+        self.add_row(ModRow::Header);
+        for addon in addons {
+            self.add_row(ModRow::new_installed(addon));
         }
 
         self.format_table();
@@ -221,28 +450,261 @@ impl ModTable {
     }
 
     pub fn print_table(&mut self) {
-        for line in &self.formatted_rows {
-            println!("{}", line);
-        }
+        self.print_rows(0, self.formatted_rows.len());
     }
 
+    /// Number of pages the table view has, an empty table still has one
+    pub fn page_count(&self) -> usize {
+        page_count(self.formatted_rows.len(), self.row_view_count)
+    }
+
+    /// Prints the current page of mods under the header
     pub fn print_table_view(&mut self) {
-        let end_index = std::cmp::min(self.row_index + self.row_view_count, self.formatted_rows.len());
-        println!("\nPrinting rows {} to {} of {}", self.row_index, end_index-1, self.formatted_rows.len());
-        for line in &self.formatted_rows[self.row_index..end_index-1] {
-            println!("{}", line);
+        let (start, end) = page_bounds(self.page, self.formatted_rows.len(), self.row_view_count);
+        self.print_rows(start, end);
+        println!(
+            "Page {} of {} (mods {} to {} of {})",
+            self.page + 1,
+            self.page_count(),
+            if end > start { start + 1 } else { 0 },
+            end,
+            self.formatted_rows.len()
+        );
+    }
+
+    /// Shows the table a page at a time, reading page commands from `input` until the user
+    /// quits, the input closes or the command is cancelled. Tables that fit on one page are
+    /// printed without asking.
+    pub async fn page_table(
+        &mut self,
+        input: &mut UnboundedReceiver<String>,
+        cancel: &CancellationToken,
+    ) {
+        self.page = 0;
+        if self.page_count() == 1 {
+            self.print_table();
+            return;
+        }
+
+        loop {
+            self.print_table_view();
+            print!("[n]ext, [p]revious, [f]irst, [l]ast, page number or [q]uit: \x1b[?25h");
+            io::stdout().flush().ok();
+
+            let line = loop {
+                tokio::select! {
+                    line = input.recv() => break line,
+                    // nothing else wakes the pager when ctrl+c cancels the command
+                    _ = tokio::time::sleep(Duration::from_millis(100)) => {
+                        if cancel.is_cancelled() {
+                            return;
+                        }
+                    }
+                }
+            };
+            let Some(line) = line else {
+                return;
+            };
+
+            match PageCommand::parse(&line) {
+                Some(PageCommand::Quit) => return,
+                Some(command) => match turn_page(command, self.page, self.page_count()) {
+                    Ok(page) => self.page = page,
+                    Err(message) => println!("{}", message),
+                },
+                None => println!("Unknown page command '{}'", line.trim()),
+            }
+        }
+    }
+
+    fn print_rows(&self, start: usize, end: usize) {
+        println!("{}", self.separator);
+        println!("{}", self.formatted_header);
+        println!("{}", self.separator);
+        for row in &self.formatted_rows[start..end] {
+            println!("{}", row);
+            println!("{}", self.separator);
         }
-        self.row_index = end_index;
+    }
+
+    /// Picks a width for each visible column. Columns start at the width of their widest
+    /// cell, then the flexible ones shrink, widest first, until the table fits the terminal.
+    fn column_widths(&self) -> Vec<(Column, usize)> {
+        let mut widths: Vec<(Column, usize)> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let (min, max) = column.width_range();
+                let widest = self
+                    .rows
+                    .iter()
+                    .map(|row| row.cell(*column).width())
+                    .max()
+                    .unwrap_or(0);
+                (*column, widest.clamp(min, max))
+            })
+            .collect();
+
+        // every column adds "| " before and " " after its text, plus the closing "|"
+        let borders = widths.len() * 3 + 1;
+        let available = self.width.saturating_sub(borders);
+        while widths.iter().map(|(_, width)| width).sum::<usize>() > available {
+            let widest = widths
+                .iter_mut()
+                .filter(|(column, width)| column.shrinks() && *width > column.width_range().0)
+                .max_by_key(|(_, width)| *width);
+            match widest {
+                Some((_, width)) => *width -= 1,
+                None => break,
+            }
+        }
+
+        widths
     }
 
     pub fn format_table(&mut self) {
-        let blank_row = "|--------------|--------------------------------|--------------------------------|----------------------------------------------------|----------------|";
+        let widths = self.column_widths();
+        self.separator = format!(
+            "|{}|",
+            widths
+                .iter()
+                .map(|(_, width)| "-".repeat(width + 2))
+                .collect::<Vec<_>>()
+                .join("|")
+        );
         self.formatted_rows.clear();
 
-        self.formatted_rows.push(format!("{}", blank_row));
         for row in &self.rows {
-            self.formatted_rows.push(format!("{}", row.format_row()));
-            self.formatted_rows.push(format!("{}", blank_row));
+            let formatted = row.format_row(&widths, self.overflow);
+            match row {
+                ModRow::Header => self.formatted_header = formatted,
+                ModRow::Data { .. } => self.formatted_rows.push(formatted),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(columns: &[Column], width: usize, summary: &str) -> ModTable {
+        ModTable {
+            rows: vec![
+                ModRow::Header,
+                ModRow::new_data("1", "Details", "1.0", summary, 5),
+            ],
+            formatted_header: String::new(),
+            formatted_rows: Vec::new(),
+            separator: String::new(),
+            row_view_count: TABLE_VIEW_ROWS,
+            page: 0,
+            columns: columns.to_vec(),
+            overflow: Overflow::Wrap,
+            width,
+        }
+    }
+
+    #[test]
+    fn wrap_breaks_at_words_and_splits_long_words() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("hi abcdefg x", 4), ["hi", "abcd", "efg", "x"]);
+        assert_eq!(wrap("", 4), [""]);
+    }
+
+    #[test]
+    fn wrap_gives_wide_characters_a_line_each_when_the_column_is_narrower() {
+        assert_eq!(wrap("日本語", 4), ["日本", "語"]);
+        assert_eq!(wrap("日本語", 1), ["日", "本", "語"]);
+        assert_eq!(wrap("ab", 0), ["a", "b"]);
+    }
+
+    #[test]
+    fn truncate_cuts_by_display_width() {
+        assert_eq!(truncate("short", 8), "short");
+        assert_eq!(truncate("a   b\nc", 8), "a b c");
+        assert_eq!(truncate("Hello world", 8), "Hello w…");
+        assert_eq!(truncate("日本語テキスト", 5), "日本…");
+        assert_eq!(pad("日本", 5), "日本 ");
+    }
+
+    #[test]
+    fn format_row_wraps_cells_onto_extra_lines() {
+        let row = ModRow::new_data("1", "Details", "1.0", "one two three", 5);
+        assert_eq!(
+            row.format_row(&[(Column::Id, 6), (Column::Summary, 7)], Overflow::Wrap),
+            "| 1      | one two |\n|        | three   |"
+        );
+        assert_eq!(
+            row.format_row(&[(Column::Id, 6), (Column::Summary, 7)], Overflow::Truncate),
+            "| 1      | one tw… |"
+        );
+    }
+
+    #[test]
+    fn tables_shorter_than_a_page_have_one_page() {
+        assert_eq!(page_count(0, 20), 1);
+        assert_eq!(page_count(5, 20), 1);
+        assert_eq!(page_count(20, 20), 1);
+        assert_eq!(page_count(21, 20), 2);
+        assert_eq!(page_bounds(0, 5, 20), (0, 5));
+        assert_eq!(page_bounds(0, 0, 20), (0, 0));
+    }
+
+    #[test]
+    fn page_bounds_end_at_the_last_entry() {
+        assert_eq!(page_bounds(1, 45, 20), (20, 40));
+        assert_eq!(page_bounds(2, 45, 20), (40, 45));
+        assert_eq!(page_bounds(3, 45, 20), (45, 45));
+    }
+
+    #[test]
+    fn turn_page_stays_within_the_pages() {
+        assert_eq!(turn_page(PageCommand::Next, 0, 3), Ok(1));
+        assert_eq!(turn_page(PageCommand::Next, 2, 3), Ok(2));
+        assert_eq!(turn_page(PageCommand::Previous, 0, 3), Ok(0));
+        assert_eq!(turn_page(PageCommand::Last, 0, 3), Ok(2));
+        assert_eq!(turn_page(PageCommand::First, 2, 3), Ok(0));
+        assert_eq!(turn_page(PageCommand::Page(3), 0, 3), Ok(2));
+        assert!(turn_page(PageCommand::Page(4), 1, 3).is_err());
+        assert!(turn_page(PageCommand::Page(0), 1, 3).is_err());
+    }
+
+    #[test]
+    fn page_commands_parse_short_and_long_forms() {
+        assert_eq!(PageCommand::parse(""), Some(PageCommand::Next));
+        assert_eq!(PageCommand::parse(" Prev "), Some(PageCommand::Previous));
+        assert_eq!(PageCommand::parse("l"), Some(PageCommand::Last));
+        assert_eq!(PageCommand::parse("12"), Some(PageCommand::Page(12)));
+        assert_eq!(PageCommand::parse("q"), Some(PageCommand::Quit));
+        assert_eq!(PageCommand::parse("x"), None);
+    }
+
+    #[test]
+    fn column_widths_fit_the_widest_cell_up_to_the_maximum() {
+        let columns = [Column::Id, Column::Name, Column::Summary];
+        let widths = table(&columns, 200, &"x".repeat(100)).column_widths();
+        assert_eq!(
+            widths,
+            [(Column::Id, 6), (Column::Name, 12), (Column::Summary, 60)]
+        );
+    }
+
+    #[test]
+    fn column_widths_shrink_the_widest_flexible_column_to_fit() {
+        let columns = [Column::Id, Column::Name, Column::Summary];
+        // 3 columns take 10 characters of borders, leaving 40 for text
+        let widths = table(&columns, 50, &"x".repeat(100)).column_widths();
+        assert_eq!(
+            widths,
+            [(Column::Id, 6), (Column::Name, 12), (Column::Summary, 22)]
+        );
+        // too narrow for the minimums, columns stop shrinking there
+        let widths = table(&columns, 20, &"x".repeat(100)).column_widths();
+        assert_eq!(
+            widths,
+            [(Column::Id, 6), (Column::Name, 12), (Column::Summary, 12)]
+        );
+    }
+}
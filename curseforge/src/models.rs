@@ -44,6 +44,17 @@ pub struct ModArrayResponse {
     pub pagination: Pagination,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModFileResponse {
+    pub data: ModFile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModFileArrayResponse {
+    pub data: Vec<ModFile>,
+    pub pagination: Pagination,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mod {
     pub screenshots: Vec<Screenshot>,
@@ -258,3 +269,34 @@ pub struct Dependency {
     #[serde(rename = "relationType")]
     pub relation_type: u32,
 }
+
+/// Response of endpoints that return a single string, e.g. changelogs and descriptions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StringResponse {
+    pub data: String,
+}
+
+/// Response of the batch mods endpoint, which is not paginated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModListResponse {
+    pub data: Vec<Mod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintMatchesResponse {
+    pub data: FingerprintMatches,
+}
+
+/// Files whose fingerprints matched those that were looked up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintMatches {
+    #[serde(rename = "exactMatches")]
+    pub exact_matches: Vec<FingerprintMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintMatch {
+    /// Id of the mod the file belongs to
+    pub id: u32,
+    pub file: ModFile,
+}
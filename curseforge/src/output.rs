@@ -0,0 +1,204 @@
+use crate::game_version;
+use crate::models::Mod;
+use crate::providers::AddonInfo;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io;
+
+/// How command results are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable tables and messages
+    #[default]
+    Table,
+    /// A json array of result objects
+    Json,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+/// Prints results as json or csv on stdout. Returns false for table output, in which case the
+/// caller renders its own human readable view.
+pub fn print_structured<T: Serialize>(
+    format: OutputFormat,
+    rows: &[T],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => return Ok(false),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Prints a failed command's error in the requested format on stderr
+pub fn print_error(format: OutputFormat, error: &str, interrupted: bool) {
+    let output = ErrorOutput {
+        error: error.to_string(),
+        interrupted,
+    };
+    match format {
+        OutputFormat::Table => eprintln!("Error: {}", error),
+        OutputFormat::Json => eprintln!(
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_default()
+        ),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stderr());
+            writer.serialize(&output).ok();
+            writer.flush().ok();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorOutput {
+    pub error: String,
+    pub interrupted: bool,
+}
+
+/// An addon returned by `search`
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub provider: String,
+    pub id: String,
+    pub name: String,
+    pub summary: String,
+    pub latest_file: String,
+    pub download_count: u64,
+    pub website_url: String,
+}
+
+impl From<&AddonInfo> for SearchHit {
+    fn from(info: &AddonInfo) -> Self {
+        Self {
+            provider: info.provider.to_string(),
+            id: info.id.clone(),
+            name: info.name.clone(),
+            summary: info.summary.clone(),
+            latest_file: info.latest_version.clone(),
+            download_count: info.download_count,
+            website_url: info.website_url.clone(),
+        }
+    }
+}
+
+/// An addon installed or restored by `get` or `rollback`
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallResult {
+    pub id: u32,
+    pub name: String,
+    pub file_id: u32,
+    pub version: String,
+    /// AddOns folders, separated by `;`
+    pub folders: String,
+}
+
+/// One row of the list commands for backups, snapshots and the cache
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveRow {
+    pub name: String,
+    pub file_id: Option<u32>,
+    /// Version for addon backups, content hash for cache entries, folder count otherwise
+    pub detail: String,
+    pub date: String,
+    pub size_bytes: Option<u64>,
+}
+
+/// One group of addons in `group list`. Lists are separated by `;`.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupRow {
+    pub name: String,
+    pub disabled: bool,
+    pub ids: String,
+    pub addons: String,
+}
+
+/// Whether an addon is enabled for a character, one row per character and addon
+#[derive(Debug, Clone, Serialize)]
+pub struct CharacterAddonRow {
+    pub account: String,
+    pub realm: String,
+    pub character: String,
+    pub folder: String,
+    pub enabled: bool,
+}
+
+/// One addon that differs between two snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiffRow {
+    pub change: String,
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Everything `info` shows about a mod. Lists are separated by `;`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModDetails {
+    pub id: u32,
+    pub name: String,
+    pub summary: String,
+    pub authors: String,
+    pub categories: String,
+    pub download_count: u32,
+    pub thumbs_up_count: u32,
+    pub date_released: String,
+    pub date_modified: String,
+    pub website_url: String,
+    pub source_url: String,
+    pub issues_url: String,
+    pub wiki_url: String,
+    pub screenshots: String,
+    /// `game version=file name` for the newest file of each game version
+    pub latest_files: String,
+    /// The description converted to plain text
+    pub description: String,
+}
+
+impl ModDetails {
+    pub fn new(game_mod: &Mod, description: &str) -> Self {
+        let join = |values: Vec<&str>| values.join(";");
+        Self {
+            id: game_mod.id,
+            name: game_mod.name.clone(),
+            summary: game_mod.summary.clone(),
+            authors: join(game_mod.authors.iter().map(|a| a.name.as_str()).collect()),
+            categories: join(
+                game_mod
+                    .categories
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect(),
+            ),
+            download_count: game_mod.download_count,
+            thumbs_up_count: game_mod.thumbs_up_count,
+            date_released: game_mod.date_released.clone(),
+            date_modified: game_mod.date_modified.clone(),
+            website_url: game_mod.links.website_url.clone().unwrap_or_default(),
+            source_url: game_mod.links.source_url.clone().unwrap_or_default(),
+            issues_url: game_mod.links.issues_url.clone().unwrap_or_default(),
+            wiki_url: game_mod.links.wiki_url.clone().unwrap_or_default(),
+            screenshots: join(
+                game_mod
+                    .screenshots
+                    .iter()
+                    .map(|s| s.url.as_str())
+                    .collect(),
+            ),
+            latest_files: game_version::latest_files_by_game_version(game_mod)
+                .iter()
+                .map(|file| format!("{}={}", file.game_version, file.filename))
+                .collect::<Vec<_>>()
+                .join(";"),
+            description: description.to_string(),
+        }
+    }
+}
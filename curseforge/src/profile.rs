@@ -0,0 +1,196 @@
+use crate::cancellation::CancellationToken;
+use crate::manifest::{now_timestamp, Manifest, ManifestEntry};
+use crate::providers::wago::{self, ReleaseChannel};
+use crate::providers::{self, AddonFile, AddonInfo, AddonProvider, FileRef};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Version of the profile format `export` writes
+const PROFILE_FORMAT: u32 = 1;
+
+/// A portable list of installed addons that `import` can reproduce on another machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub format: u32,
+    pub exported_at: u64,
+    pub addons: Vec<ProfileAddon>,
+}
+
+/// An addon in a profile and the file that was installed when it was exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileAddon {
+    pub provider: String,
+    /// Id of the addon at its provider
+    pub id: String,
+    pub name: String,
+    /// Provider file id, 0 when the provider doesn't number its files
+    #[serde(default)]
+    pub file_id: u32,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_tag: Option<String>,
+    /// Wago release channel to install from when importing the latest files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Always installed at the exported file, even when importing the latest files
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl ProfileAddon {
+    fn file_ref(&self) -> FileRef {
+        FileRef {
+            id: self.file_id,
+            release_tag: self.release_tag.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
+
+/// Which files `import` installs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// The files in the profile
+    Exact,
+    /// The newest files for the installed game version, except for pinned addons
+    Latest,
+}
+
+/// Builds a profile of the addons in the manifest. Addons installed from local files or
+/// linked for development can't be downloaded elsewhere and are left out.
+pub fn export_profile(pinned: &[u32]) -> Result<Profile, Box<dyn std::error::Error>> {
+    let manifest = Manifest::load()?;
+    let mut addons = Vec::new();
+    for entry in &manifest.addons {
+        if !providers::has_remote(&entry.provider) {
+            eprintln!(
+                "Leaving out {}, it was installed from {}",
+                entry.name, entry.file_name
+            );
+            continue;
+        }
+        addons.push(ProfileAddon {
+            provider: entry.provider.clone(),
+            id: entry.provider_id(),
+            name: entry.name.clone(),
+            file_id: entry.file_id,
+            version: entry.display_name.clone(),
+            release_tag: entry.release_tag.clone(),
+            channel: (entry.provider == wago::NAME)
+                .then(|| ReleaseChannel::configured().map(|channel| channel.name().to_string()))
+                .transpose()?,
+            pinned: pinned.contains(&entry.mod_id),
+        });
+    }
+    addons.sort_by_key(|addon| addon.name.to_lowercase());
+
+    Ok(Profile {
+        format: PROFILE_FORMAT,
+        exported_at: now_timestamp(),
+        addons,
+    })
+}
+
+pub fn read_profile(path: &Path) -> Result<Profile, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let profile: Profile = serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not an addon profile: {}", path.display(), e))?;
+    if profile.format > PROFILE_FORMAT {
+        return Err(format!(
+            "{} was exported by a newer version of Addown (format {})",
+            path.display(),
+            profile.format
+        )
+        .into());
+    }
+    Ok(profile)
+}
+
+/// Installs the addons of a profile, skipping those whose file is already installed. An
+/// addon that fails is reported and the rest are still installed, stopping between addons
+/// if cancelled.
+pub async fn import_profile(
+    profile: &Profile,
+    mode: ImportMode,
+    cancel: &CancellationToken,
+) -> Result<Vec<ManifestEntry>, Box<dyn std::error::Error>> {
+    let mut installed = Vec::new();
+    let mut failed = 0;
+    for addon in &profile.addons {
+        cancel.check()?;
+        match import_addon(addon, mode, cancel).await {
+            Ok(Some(entry)) => installed.push(entry),
+            Ok(None) => {}
+            Err(e) => {
+                cancel.check()?;
+                eprintln!("Could not import {}: {}", addon.name, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{} addon(s) could not be imported", failed);
+    }
+
+    Ok(installed)
+}
+
+/// Installs one addon of a profile, or returns None when its file is already installed
+async fn import_addon(
+    addon: &ProfileAddon,
+    mode: ImportMode,
+    cancel: &CancellationToken,
+) -> Result<Option<ManifestEntry>, Box<dyn std::error::Error>> {
+    if !providers::has_remote(&addon.provider) {
+        return Err(format!("addons from {} can't be downloaded", addon.provider).into());
+    }
+    let provider = providers::provider(&addon.provider)?;
+    let mod_id = provider.local_id(&addon.id)?;
+    let target = cancel
+        .run(target_file(provider.as_ref(), addon, mode))
+        .await?;
+
+    let manifest = Manifest::load()?;
+    if let Some(entry) = manifest.find(mod_id) {
+        if FileRef::from(entry).matches(&target) {
+            eprintln!("{} {} is already installed", entry.name, entry.display_name);
+            return Ok(None);
+        }
+    }
+
+    // the profile has everything the manifest needs, so the addon isn't looked up again
+    let info = AddonInfo {
+        provider: provider.name(),
+        id: addon.id.clone(),
+        name: addon.name.clone(),
+        summary: String::new(),
+        authors: Vec::new(),
+        website_url: String::new(),
+        download_count: 0,
+        latest_version: target.display_name.clone(),
+    };
+    Ok(Some(
+        providers::install_file(&info, mod_id, &target, cancel).await?,
+    ))
+}
+
+async fn target_file(
+    provider: &dyn AddonProvider,
+    addon: &ProfileAddon,
+    mode: ImportMode,
+) -> Result<AddonFile, Box<dyn std::error::Error>> {
+    if mode == ImportMode::Exact || addon.pinned {
+        return provider.find_file(&addon.id, &addon.file_ref()).await;
+    }
+    match addon.channel.as_deref() {
+        Some(channel) if addon.provider == wago::NAME => {
+            let channel = ReleaseChannel::parse(channel)
+                .ok_or_else(|| format!("unknown Wago release channel '{}'", channel))?;
+            wago::resolve_in_channel(&addon.id, channel).await
+        }
+        _ => provider.resolve_download(&addon.id).await,
+    }
+}
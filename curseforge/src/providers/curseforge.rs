@@ -0,0 +1,126 @@
+use super::{AddonFile, AddonInfo, AddonProvider, FileRef};
+use crate::curseforge_api;
+use crate::game_version;
+use crate::models::{Mod, ModFile};
+use async_trait::async_trait;
+
+/// CurseForge game id for World of Warcraft
+pub const WOW_GAME_ID: i32 = 1;
+/// CurseForge hash algorithm id for sha1
+const SHA1_ALGO: u32 = 1;
+
+/// Addons from curseforge.com
+pub struct CurseForge;
+
+#[async_trait]
+impl AddonProvider for CurseForge {
+    fn name(&self) -> &'static str {
+        super::DEFAULT_PROVIDER
+    }
+
+    fn local_id(&self, id: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        parse_id(id)
+    }
+
+    async fn search(&self, filter: &str) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let mods = curseforge_api::search_mods(WOW_GAME_ID, filter).await?;
+        Ok(mods.iter().map(addon_info).collect())
+    }
+
+    async fn get_info(&self, id: &str) -> Result<AddonInfo, Box<dyn std::error::Error>> {
+        let mod_id = parse_id(id)?;
+        let game_mod = curseforge_api::get_mod_info(mod_id).await?;
+        Ok(addon_info(&game_mod))
+    }
+
+    async fn list_files(&self, id: &str) -> Result<Vec<AddonFile>, Box<dyn std::error::Error>> {
+        let mod_id = parse_id(id)?;
+        let files = curseforge_api::get_mod_files(mod_id).await?;
+        Ok(files.iter().map(addon_file).collect())
+    }
+
+    async fn resolve_download(&self, id: &str) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let mod_id = parse_id(id)?;
+        let game_mod = curseforge_api::get_mod_info(mod_id).await?;
+        let mod_file = game_version::get_mod_file_for_game_version(&game_mod)?;
+        Ok(addon_file(mod_file))
+    }
+
+    async fn find_file(
+        &self,
+        id: &str,
+        file: &FileRef,
+    ) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let mod_id = parse_id(id)?;
+        let mod_file = curseforge_api::get_mod_file_info(mod_id, file.id).await?;
+        Ok(addon_file(&mod_file))
+    }
+
+    async fn batch_lookup(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let ids = ids
+            .iter()
+            .map(|id| parse_id(id))
+            .collect::<Result<Vec<u32>, _>>()?;
+        let mods = curseforge_api::get_mods(&ids).await?;
+        Ok(mods.iter().map(addon_info).collect())
+    }
+}
+
+fn parse_id(id: &str) -> Result<u32, Box<dyn std::error::Error>> {
+    id.trim()
+        .parse()
+        .map_err(|_| format!("Invalid CurseForge id: {}", id).into())
+}
+
+pub fn addon_info(game_mod: &Mod) -> AddonInfo {
+    AddonInfo {
+        provider: super::DEFAULT_PROVIDER,
+        id: game_mod.id.to_string(),
+        name: game_mod.name.clone(),
+        summary: game_mod.summary.clone(),
+        authors: game_mod
+            .authors
+            .iter()
+            .map(|author| author.name.clone())
+            .collect(),
+        website_url: game_mod.links.website_url.clone().unwrap_or_default(),
+        download_count: game_mod.download_count as u64,
+        latest_version: game_mod
+            .latest_files
+            .first()
+            .map(|file| file.display_name.clone())
+            .unwrap_or_default(),
+    }
+}
+
+pub fn addon_file(mod_file: &ModFile) -> AddonFile {
+    AddonFile {
+        id: mod_file.id,
+        display_name: mod_file.display_name.clone(),
+        file_name: mod_file.file_name.clone(),
+        download_url: cdn_url(mod_file),
+        sha1: mod_file
+            .hashes
+            .iter()
+            .find(|hash| hash.algo == SHA1_ALGO)
+            .map(|hash| hash.value.clone()),
+        game_versions: mod_file.game_versions.clone(),
+        released: mod_file.file_date.clone(),
+        release_tag: None,
+    }
+}
+
+/// Files are served from the CDN by id, split after the first four digits, and name
+fn cdn_url(mod_file: &ModFile) -> String {
+    let file_id_str = mod_file.id.to_string();
+    let (prefix, suffix) = file_id_str.split_at(4.min(file_id_str.len()));
+    format!(
+        "https://edge.forgecdn.net/files/{}/{}/{}",
+        prefix,
+        suffix,
+        urlencoding::encode(&mod_file.file_name)
+    )
+}
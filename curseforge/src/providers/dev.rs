@@ -0,0 +1,281 @@
+use crate::backup;
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::curseforge_api;
+use crate::manifest::{now_timestamp, Manifest, ManifestEntry};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Provider recorded for working copies linked into AddOns with `link`. They are never
+/// updated or matched against a provider.
+pub const NAME: &str = "dev";
+
+/// Suffixes of tocs for a single game flavor, e.g. `MyAddon_Mainline.toc`
+const FLAVOR_SUFFIXES: [&str; 6] = [
+    "_mainline",
+    "_classic",
+    "_vanilla",
+    "_tbc",
+    "_wrath",
+    "_cata",
+];
+
+/// What `unlink` removed and restored
+pub struct Unlinked {
+    pub link: ManifestEntry,
+    /// Releases that were installed before the link and are back in place
+    pub restored: Vec<ManifestEntry>,
+}
+
+/// Symlinks the addon folders of a working copy into AddOns. A working copy with a toc is one
+/// addon, linked under its toc's name; otherwise every sub folder with a toc is linked.
+/// Installed releases of the same folders are backed up and removed so `unlink` can put
+/// them back.
+pub fn link(repo: &Path) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let repo = repo.canonicalize()?;
+    let links = addon_folders(&repo)?;
+    if links.is_empty() {
+        return Err(format!("{} has no addon .toc files", repo.display()).into());
+    }
+    let source_id = repo.display().to_string();
+    let mod_id = super::hashed_id(NAME, &source_id);
+    let names: Vec<String> = links.iter().map(|(name, _)| name.clone()).collect();
+
+    let mut manifest = Manifest::load()?;
+    if let Some(linked) = manifest.find(mod_id) {
+        return Err(format!("{} is already linked", linked.file_name).into());
+    }
+    let replaced: Vec<ManifestEntry> = manifest
+        .addons
+        .iter()
+        .filter(|entry| entry.directories.iter().any(|dir| names.contains(dir)))
+        .cloned()
+        .collect();
+    if let Some(linked) = replaced.iter().find(|entry| entry.provider == NAME) {
+        return Err(format!(
+            "{} is already linked from {}",
+            linked.name, linked.file_name
+        )
+        .into());
+    }
+
+    // unlink restores what is in the way from backups, which are turned off
+    let in_the_way = names
+        .iter()
+        .any(|name| fs::symlink_metadata(Path::new(&config.addons_path()).join(name)).is_ok());
+    if in_the_way && config.backup_count == 0 {
+        return Err(
+            "ADDOWN_BACKUP_COUNT is 0, so what the link replaces could not be restored by unlink"
+                .into(),
+        );
+    }
+
+    // releases Addown installed are backed up with their manifest entry, anything else that
+    // is in the way under the link's id
+    for entry in &replaced {
+        backup::backup_installed(entry.mod_id, &[])?;
+        for dir in &entry.directories {
+            fs::remove_dir_all(Path::new(&config.addons_path()).join(dir)).ok();
+        }
+        manifest.remove(entry.mod_id);
+    }
+    backup::backup_installed(mod_id, &names)?;
+
+    for (name, target) in &links {
+        let link_path = Path::new(&config.addons_path()).join(name);
+        remove_link_or_dir(&link_path)?;
+        symlink_dir(target, &link_path)?;
+        eprintln!("Linked {} to {}", link_path.display(), target.display());
+    }
+
+    let entry = ManifestEntry {
+        mod_id,
+        name: names[0].clone(),
+        file_id: 0,
+        file_name: source_id.clone(),
+        display_name: branch(&repo)
+            .map(|branch| format!("dev ({})", branch))
+            .unwrap_or_else(|| "dev".to_string()),
+        directories: names,
+        installed_at: now_timestamp(),
+        provider: NAME.to_string(),
+        source_id,
+        release_tag: None,
+        replaced: replaced.iter().map(|entry| entry.mod_id).collect(),
+    };
+    manifest.upsert(entry.clone());
+    manifest.save()?;
+
+    Ok(entry)
+}
+
+/// Removes the links of a working copy, found by its local id or path, and restores what
+/// was installed before it was linked
+pub fn unlink(
+    target: &str,
+    cancel: &CancellationToken,
+) -> Result<Unlinked, Box<dyn std::error::Error>> {
+    let config = CurseForgeConfig::get();
+    let mut manifest = Manifest::load()?;
+    let path = Path::new(target)
+        .canonicalize()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    let Some(link) = manifest
+        .addons
+        .iter()
+        .filter(|entry| entry.provider == NAME)
+        .find(|entry| {
+            entry.mod_id.to_string() == target || entry.source_id == path || entry.name == target
+        })
+        .cloned()
+    else {
+        return Err(format!("No linked addon matches {}", target).into());
+    };
+
+    for dir in &link.directories {
+        let link_path = Path::new(&config.addons_path()).join(dir);
+        if is_link(&link_path) {
+            remove_link_or_dir(&link_path)?;
+        }
+    }
+    manifest.remove(link.mod_id);
+    manifest.save()?;
+
+    let mut restored = Vec::new();
+    for mod_id in &link.replaced {
+        restored.push(backup::rollback(*mod_id, None, cancel)?);
+    }
+    // folders Addown didn't track go back as they were, without a manifest entry
+    if let Some(untracked) = backup::list_backups(link.mod_id)?.first() {
+        curseforge_api::unzip_file(
+            &untracked.path.to_string_lossy(),
+            &config.addons_path(),
+            cancel,
+        )?;
+        eprintln!("Restored {}", untracked.path.display());
+        fs::remove_dir_all(untracked.path.parent().unwrap_or(&untracked.path)).ok();
+    }
+
+    Ok(Unlinked { link, restored })
+}
+
+/// Addon folders of a working copy, as the name to link in AddOns and the folder to link to
+fn addon_folders(repo: &Path) -> Result<Vec<(String, PathBuf)>, Box<dyn std::error::Error>> {
+    if let Some(name) = toc_name(repo) {
+        return Ok(vec![(name, repo.to_path_buf())]);
+    }
+
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(repo)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(name) = toc_name(&path) {
+                folders.push((name, path));
+            }
+        }
+    }
+    folders.sort();
+    Ok(folders)
+}
+
+/// The folder name WoW expects for the addon whose tocs are in `dir`, without any flavor suffix
+fn toc_name(dir: &Path) -> Option<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("toc"))
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .map(|stem| {
+            let lower = stem.to_lowercase();
+            match FLAVOR_SUFFIXES
+                .iter()
+                .find(|suffix| lower.ends_with(*suffix))
+            {
+                Some(suffix) => stem[..stem.len() - suffix.len()].to_string(),
+                None => stem,
+            }
+        })
+        .collect();
+    names.sort_by_key(|name| name.len());
+    names.into_iter().next()
+}
+
+/// The branch checked out in a git working copy
+fn branch(repo: &Path) -> Option<String> {
+    let head = fs::read_to_string(repo.join(".git").join("HEAD")).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(str::to_string)
+}
+
+/// Whether a path is a symlink, or a junction on Windows
+pub fn is_link(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+        || fs::read_link(path).is_ok()
+}
+
+fn remove_link_or_dir(path: &Path) -> io::Result<()> {
+    if is_link(path) {
+        // directory links on Windows are removed like directories, without their contents
+        return fs::remove_file(path).or_else(|_| fs::remove_dir(path));
+    }
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(unix)]
+fn symlink_dir(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink_dir(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_dir(target, link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn addon_dir(name: &str, tocs: &[&str]) -> TempDir {
+        let dir = TempDir::new(&format!("dev-{}", name));
+        for toc in tocs {
+            fs::write(dir.join(toc), "## Title: Test\n").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn toc_name_strips_flavor_suffixes() {
+        let dir = addon_dir("flavors", &["MyAddon_Mainline.toc", "MyAddon-Classic.TOC"]);
+        assert_eq!(toc_name(dir.path()).as_deref(), Some("MyAddon"));
+    }
+
+    #[test]
+    fn toc_name_prefers_the_shortest_name() {
+        let dir = addon_dir(
+            "shortest",
+            &["MyAddon_Options.toc", "MyAddon.toc", "README.md"],
+        );
+        assert_eq!(toc_name(dir.path()).as_deref(), Some("MyAddon"));
+    }
+
+    #[test]
+    fn toc_name_without_toc_is_none() {
+        let dir = addon_dir("none", &["README.md"]);
+        assert_eq!(toc_name(dir.path()), None);
+        let removed = dir.path().to_path_buf();
+        drop(dir);
+        assert_eq!(toc_name(&removed), None);
+    }
+}
@@ -0,0 +1,272 @@
+use super::{AddonFile, AddonInfo, AddonProvider, FileRef};
+use crate::config::CurseForgeConfig;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Name of the provider in the manifest and for `get --source`
+pub const NAME: &str = "github";
+
+const API_URL: &str = "https://api.github.com";
+/// The BigWigs packager's name for the retail flavor
+const FLAVOR: &str = "mainline";
+/// Asset name parts of the packager's builds for other flavors
+const OTHER_FLAVORS: [&str; 9] = [
+    "classic", "vanilla", "bcc", "tbc", "wrath", "wotlk", "cata", "mists", "nolib",
+];
+/// Most releases `list_files` looks at
+const MAX_RELEASES: usize = 10;
+
+/// Addons published as GitHub release assets, identified by `owner/repo`
+pub struct GitHub;
+
+#[derive(Debug, Clone, Deserialize)]
+struct Repository {
+    full_name: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    html_url: String,
+    owner: Owner,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Owner {
+    login: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SearchResponse {
+    items: Vec<Repository>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// The `release.json` the BigWigs packager uploads next to its builds
+#[derive(Debug, Clone, Deserialize)]
+struct PackagerRelease {
+    releases: Vec<PackagedFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PackagedFile {
+    filename: String,
+    #[serde(default)]
+    nolib: bool,
+    #[serde(default)]
+    metadata: Vec<PackagedFlavor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PackagedFlavor {
+    flavor: String,
+}
+
+#[async_trait]
+impl AddonProvider for GitHub {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    /// Repositories are known by their lowercase `owner/repo`, however they were typed
+    fn local_id(&self, id: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(super::hashed_id(NAME, &parse_repo(id)?.to_lowercase()))
+    }
+
+    async fn search(&self, filter: &str) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let url = format!(
+            "{}/search/repositories?q={}+in:name&sort=stars&per_page=30",
+            API_URL,
+            urlencoding::encode(filter)
+        );
+        let response: SearchResponse = get(&url).await?;
+        Ok(response
+            .items
+            .iter()
+            .map(|repository| repository_info(repository, String::new()))
+            .collect())
+    }
+
+    async fn get_info(&self, id: &str) -> Result<AddonInfo, Box<dyn std::error::Error>> {
+        let repo = parse_repo(id)?;
+        let repository: Repository = get(&format!("{}/repos/{}", API_URL, repo)).await?;
+        let latest_tag = match latest_release(&repo).await {
+            Ok(release) => release.tag_name,
+            Err(_) => String::new(),
+        };
+        Ok(repository_info(&repository, latest_tag))
+    }
+
+    async fn list_files(&self, id: &str) -> Result<Vec<AddonFile>, Box<dyn std::error::Error>> {
+        let repo = parse_repo(id)?;
+        let url = format!(
+            "{}/repos/{}/releases?per_page={}",
+            API_URL, repo, MAX_RELEASES
+        );
+        let releases: Vec<Release> = get(&url).await?;
+        let mut files = Vec::new();
+        for release in releases.iter().filter(|release| !release.draft) {
+            if let Some(asset) = pick_asset(release).await? {
+                files.push(addon_file(release, asset));
+            }
+        }
+        Ok(files)
+    }
+
+    /// The asset of the latest release built for the current flavor
+    async fn resolve_download(&self, id: &str) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let repo = parse_repo(id)?;
+        let release = latest_release(&repo).await?;
+        let Some(asset) = pick_asset(&release).await? else {
+            return Err(format!(
+                "Release {} of {} has no {} asset",
+                release.tag_name, repo, FLAVOR
+            )
+            .into());
+        };
+        Ok(addon_file(&release, asset))
+    }
+
+    /// Releases are fetched by tag, so older ones than `list_files` returns can be found
+    async fn find_file(
+        &self,
+        id: &str,
+        file: &FileRef,
+    ) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let repo = parse_repo(id)?;
+        let Some(tag) = &file.release_tag else {
+            return Err(format!("No release tag to find in {}", repo).into());
+        };
+        let url = format!(
+            "{}/repos/{}/releases/tags/{}",
+            API_URL,
+            repo,
+            urlencoding::encode(tag)
+        );
+        let release: Release = get(&url).await?;
+        let Some(asset) = pick_asset(&release).await? else {
+            return Err(format!("Release {} of {} has no {} asset", tag, repo, FLAVOR).into());
+        };
+        Ok(addon_file(&release, asset))
+    }
+
+    /// GitHub has no batch endpoint, repositories are looked up one at a time
+    async fn batch_lookup(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let mut addons = Vec::new();
+        for id in ids {
+            addons.push(self.get_info(id).await?);
+        }
+        Ok(addons)
+    }
+}
+
+/// Accepts `owner/repo` or a repository url
+fn parse_repo(id: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let repo = id
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("github.com/")
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+    match repo.split('/').collect::<Vec<_>>().as_slice() {
+        [owner, name] if !owner.is_empty() && !name.is_empty() => Ok(repo.to_string()),
+        _ => Err(format!("Invalid GitHub repository '{}', expected owner/repo", id).into()),
+    }
+}
+
+async fn get<T: DeserializeOwned>(url: &str) -> Result<T, Box<dyn std::error::Error>> {
+    let mut request = reqwest::Client::new()
+        .get(url)
+        .header("user-agent", "addown")
+        .header("accept", "application/vnd.github+json");
+    if let Some(token) = &CurseForgeConfig::get().github_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await?.error_for_status()?;
+    let response_text = response.text().await?;
+    Ok(serde_json::from_str(&response_text)?)
+}
+
+/// The newest release that is not a prerelease
+async fn latest_release(repo: &str) -> Result<Release, Box<dyn std::error::Error>> {
+    get(&format!("{}/repos/{}/releases/latest", API_URL, repo)).await
+}
+
+/// Picks the asset built for the current flavor, from the packager's `release.json` when the
+/// release has one and from the asset names otherwise
+async fn pick_asset(release: &Release) -> Result<Option<&Asset>, Box<dyn std::error::Error>> {
+    if let Some(metadata) = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == "release.json")
+    {
+        let packaged: PackagerRelease = get(&metadata.browser_download_url).await?;
+        let filename = packaged
+            .releases
+            .iter()
+            .filter(|file| !file.nolib)
+            .find(|file| file.metadata.iter().any(|meta| meta.flavor == FLAVOR))
+            .map(|file| file.filename.clone());
+        if let Some(filename) = filename {
+            return Ok(release.assets.iter().find(|asset| asset.name == filename));
+        }
+    }
+
+    let zips: Vec<&Asset> = release
+        .assets
+        .iter()
+        .filter(|asset| asset.name.to_lowercase().ends_with(".zip"))
+        .collect();
+    let named_for_flavor = zips.iter().find(|asset| {
+        let name = asset.name.to_lowercase();
+        name.contains(FLAVOR) || name.contains("retail")
+    });
+    let without_flavor = zips.iter().find(|asset| {
+        let name = asset.name.to_lowercase();
+        !OTHER_FLAVORS.iter().any(|flavor| name.contains(flavor))
+    });
+    Ok(named_for_flavor.or(without_flavor).copied())
+}
+
+fn repository_info(repository: &Repository, latest_tag: String) -> AddonInfo {
+    AddonInfo {
+        provider: NAME,
+        id: repository.full_name.clone(),
+        name: repository.name.clone(),
+        summary: repository.description.clone().unwrap_or_default(),
+        authors: vec![repository.owner.login.clone()],
+        website_url: repository.html_url.clone(),
+        download_count: 0,
+        latest_version: latest_tag,
+    }
+}
+
+fn addon_file(release: &Release, asset: &Asset) -> AddonFile {
+    AddonFile {
+        id: 0,
+        display_name: release.tag_name.clone(),
+        file_name: asset.name.clone(),
+        download_url: asset.browser_download_url.clone(),
+        sha1: None,
+        game_versions: Vec::new(),
+        released: release.published_at.clone().unwrap_or_default(),
+        release_tag: Some(release.tag_name.clone()),
+    }
+}
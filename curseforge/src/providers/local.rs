@@ -0,0 +1,127 @@
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::curseforge_api;
+use crate::installed_mods;
+use crate::manifest::{now_timestamp, Manifest, ManifestEntry};
+use std::fs;
+use std::path::Path;
+
+/// Provider recorded for addons installed from a zip or folder on disk. They have nowhere to
+/// be updated from, so `update` leaves them alone.
+pub const NAME: &str = "local";
+
+/// Installs an addon zip from disk
+pub fn install_zip(
+    zip_path: &Path,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    if !zip_path.is_file() {
+        return Err(format!("{} is not a file", zip_path.display()).into());
+    }
+    let version = zip_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    install_archive(zip_path, &zip_path.display().to_string(), &version, cancel)
+}
+
+/// Installs an addon folder from disk, or every addon folder in it when it has no toc of its
+/// own. The folders are zipped first so they go through the same extraction as downloads.
+pub fn install_dir(
+    dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let dir = dir.canonicalize()?;
+    let (base_dir, dirs) = match (has_toc(&dir), dir.parent(), dir.file_name()) {
+        (true, Some(parent), Some(name)) => (
+            parent.to_path_buf(),
+            vec![name.to_string_lossy().to_string()],
+        ),
+        _ => {
+            let mut dirs = Vec::new();
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                if entry.path().is_dir() && has_toc(&entry.path()) {
+                    dirs.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+            (dir.clone(), dirs)
+        }
+    };
+    if dirs.is_empty() {
+        return Err(format!("{} has no addon .toc files", dir.display()).into());
+    }
+
+    let version = dirs
+        .iter()
+        .find_map(|name| installed_mods::read_toc_version(&base_dir.join(name)))
+        .unwrap_or_else(|| "local".to_string());
+    let zip_path = Path::new(&CurseForgeConfig::get().cache_path())
+        .join(format!("local-{}.zip", dirs.join("-")));
+    curseforge_api::zip_dirs(&zip_path, &base_dir, &dirs, &[])?;
+    let installed = install_archive(&zip_path, &dir.display().to_string(), &version, cancel);
+    fs::remove_file(&zip_path).ok();
+
+    installed
+}
+
+/// Installs an archive and records it as local. The addon is known by its folders, so
+/// installing a newer build of the same addon replaces the older one, and any entry from
+/// another provider for the same folders is dropped so `update` won't overwrite the build.
+fn install_archive(
+    archive_path: &Path,
+    file_name: &str,
+    version: &str,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let mut dirs = curseforge_api::get_top_level_dirs(&archive_path.to_string_lossy())?;
+    if dirs.is_empty() {
+        return Err(format!("{} contains no addon folders", archive_path.display()).into());
+    }
+    dirs.sort();
+    let source_id = dirs.join(",");
+    let mod_id = super::hashed_id(NAME, &source_id);
+
+    let directories = curseforge_api::install_archive(archive_path, mod_id, cancel)?;
+    let entry = ManifestEntry {
+        mod_id,
+        name: dirs[0].clone(),
+        file_id: 0,
+        file_name: file_name.to_string(),
+        display_name: version.to_string(),
+        directories,
+        installed_at: now_timestamp(),
+        provider: NAME.to_string(),
+        source_id,
+        release_tag: None,
+        replaced: Vec::new(),
+    };
+
+    let mut manifest = Manifest::load()?;
+    manifest.addons.retain(|existing| {
+        let replaced = existing.mod_id != mod_id
+            && existing
+                .directories
+                .iter()
+                .any(|dir| entry.directories.contains(dir));
+        if replaced {
+            eprintln!("Replacing {} from {}", existing.name, existing.provider);
+        }
+        !replaced
+    });
+    manifest.upsert(entry.clone());
+    manifest.save()?;
+
+    Ok(entry)
+}
+
+fn has_toc(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("toc"))
+        })
+    })
+}
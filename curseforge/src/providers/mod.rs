@@ -0,0 +1,310 @@
+pub mod curseforge;
+pub mod dev;
+pub mod github;
+pub mod local;
+pub mod wago;
+pub mod wowinterface;
+
+use crate::cancellation::CancellationToken;
+use crate::curseforge_api;
+use crate::download_cache;
+use crate::manifest::{now_timestamp, Manifest, ManifestEntry};
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+pub use curseforge::CurseForge;
+pub use github::GitHub;
+pub use wago::Wago;
+pub use wowinterface::WowInterface;
+
+/// Provider addons come from unless another is asked for
+pub const DEFAULT_PROVIDER: &str = "curseforge";
+/// Names accepted by `provider`
+pub const PROVIDERS: [&str; 4] = [
+    DEFAULT_PROVIDER,
+    wowinterface::NAME,
+    wago::NAME,
+    github::NAME,
+];
+
+/// An addon as a provider describes it
+#[derive(Debug, Clone)]
+pub struct AddonInfo {
+    pub provider: &'static str,
+    pub id: String,
+    pub name: String,
+    pub summary: String,
+    pub authors: Vec<String>,
+    pub website_url: String,
+    pub download_count: u64,
+    /// Display name of the newest file
+    pub latest_version: String,
+}
+
+/// A file of an addon that can be downloaded and installed
+#[derive(Debug, Clone)]
+pub struct AddonFile {
+    /// Provider file id, 0 when the provider doesn't number its files
+    pub id: u32,
+    pub display_name: String,
+    pub file_name: String,
+    pub download_url: String,
+    pub sha1: Option<String>,
+    pub game_versions: Vec<String>,
+    /// Release date as reported by the provider
+    pub released: String,
+    /// Tag of the release the file belongs to, for providers that publish releases by tag
+    pub release_tag: Option<String>,
+}
+
+/// Identifies a file of an addon the way its provider can find it again
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileRef {
+    /// Provider file id, 0 when the provider doesn't number its files
+    pub id: u32,
+    pub release_tag: Option<String>,
+    pub version: String,
+}
+
+impl FileRef {
+    /// Whether a file is the one referred to, compared the same way `update` does
+    pub fn matches(&self, file: &AddonFile) -> bool {
+        if file.release_tag.is_some() {
+            self.release_tag == file.release_tag
+        } else if file.id != 0 {
+            self.id == file.id
+        } else {
+            self.version == file.display_name
+        }
+    }
+}
+
+impl std::fmt::Display for FileRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.release_tag, self.id) {
+            (Some(tag), _) => write!(f, "{}", tag),
+            (None, 0) => write!(f, "{}", self.version),
+            (None, id) => write!(f, "{} ({})", id, self.version),
+        }
+    }
+}
+
+impl From<&ManifestEntry> for FileRef {
+    fn from(entry: &ManifestEntry) -> Self {
+        Self {
+            id: entry.file_id,
+            release_tag: entry.release_tag.clone(),
+            version: entry.display_name.clone(),
+        }
+    }
+}
+
+/// A site addons can be searched for and installed from
+#[async_trait]
+pub trait AddonProvider: Send + Sync {
+    /// Name recorded in the manifest for addons installed from this provider
+    fn name(&self) -> &'static str;
+
+    /// Id the manifest, backups and commands know an addon of this provider by. Ids of
+    /// other providers are hashed with the high bit set so they never clash with CurseForge's.
+    fn local_id(&self, id: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(hashed_id(self.name(), id))
+    }
+
+    async fn search(&self, filter: &str) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>>;
+
+    async fn get_info(&self, id: &str) -> Result<AddonInfo, Box<dyn std::error::Error>>;
+
+    /// Recent files of an addon, newest first
+    async fn list_files(&self, id: &str) -> Result<Vec<AddonFile>, Box<dyn std::error::Error>>;
+
+    /// The file to install for the installed game version
+    async fn resolve_download(&self, id: &str) -> Result<AddonFile, Box<dyn std::error::Error>>;
+
+    /// A specific file of an addon, by file id or, for providers that don't number their
+    /// files, by release tag or version. Only the recent files are searched by default.
+    async fn find_file(
+        &self,
+        id: &str,
+        file: &FileRef,
+    ) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let files = self.list_files(id).await?;
+        files
+            .into_iter()
+            .find(|candidate| file.matches(candidate))
+            .ok_or_else(|| format!("{} {} has no file {}", self.name(), id, file).into())
+    }
+
+    /// Looks up several addons at once, in as few requests as the provider allows.
+    /// Unknown ids are left out of the result.
+    async fn batch_lookup(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>>;
+}
+
+/// FNV-1a hash of a provider's id for an addon, with the high bit set
+fn hashed_id(provider: &str, id: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in format!("{}:{}", provider, id.trim()).bytes() {
+        hash = (hash ^ byte as u32).wrapping_mul(0x0100_0193);
+    }
+    hash | 0x8000_0000
+}
+
+/// Finds a provider by the name recorded in the manifest
+pub fn provider(name: &str) -> Result<Box<dyn AddonProvider>, Box<dyn std::error::Error>> {
+    match name {
+        DEFAULT_PROVIDER => Ok(Box::new(CurseForge)),
+        wowinterface::NAME => Ok(Box::new(WowInterface)),
+        wago::NAME => Ok(Box::new(Wago)),
+        github::NAME => Ok(Box::new(GitHub)),
+        _ => Err(format!(
+            "Unknown provider '{}', expected one of: {}",
+            name,
+            PROVIDERS.join(", ")
+        )
+        .into()),
+    }
+}
+
+/// Whether addons of a provider can be looked up and updated. Local files and dev links
+/// have nowhere to come from.
+pub fn has_remote(provider: &str) -> bool {
+    provider != local::NAME && provider != dev::NAME
+}
+
+/// The provider an installed addon came from and the addon's id there. Addons missing from
+/// the manifest are taken to be from CurseForge.
+pub fn for_installed(
+    mod_id: u32,
+) -> Result<(Box<dyn AddonProvider>, String), Box<dyn std::error::Error>> {
+    match Manifest::load()?.find(mod_id) {
+        Some(entry) if !has_remote(&entry.provider) => {
+            Err(format!("{} was installed from {}", entry.name, entry.file_name).into())
+        }
+        Some(entry) => Ok((provider(&entry.provider)?, entry.provider_id())),
+        None => Ok((Box::new(CurseForge), mod_id.to_string())),
+    }
+}
+
+/// Installs the file of an addon that matches the installed game version. When the provider
+/// can't be reached, an addon that is already installed is reinstalled from the download
+/// cache if its recorded file is there.
+pub async fn install(
+    provider: &dyn AddonProvider,
+    id: &str,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let mod_id = provider.local_id(id)?;
+    let cached = Manifest::load()?
+        .find(mod_id)
+        .filter(|entry| entry.provider == provider.name())
+        .and_then(|entry| cached_install(provider.name(), entry));
+    let resolved = async {
+        let info = cancel.run(provider.get_info(id)).await?;
+        let file = cancel.run(provider.resolve_download(id)).await?;
+        Ok::<_, Box<dyn std::error::Error>>((info, file))
+    }
+    .await;
+    let (info, file) = match (resolved, cached) {
+        (Ok(resolved), _) => resolved,
+        (Err(e), Some(cached)) if !cancel.is_cancelled() => {
+            eprintln!(
+                "Could not reach {} ({}), reinstalling the cached {}",
+                provider.name(),
+                e,
+                cached.1.file_name
+            );
+            cached
+        }
+        (Err(e), _) => return Err(e),
+    };
+    install_file(&info, mod_id, &file, cancel).await
+}
+
+/// The addon and file a manifest entry records, if that file is in the download cache
+fn cached_install(provider: &'static str, entry: &ManifestEntry) -> Option<(AddonInfo, AddonFile)> {
+    let cached = download_cache::find_file(provider, entry.file_id)?;
+    let info = AddonInfo {
+        provider,
+        id: entry.provider_id(),
+        name: entry.name.clone(),
+        summary: String::new(),
+        authors: Vec::new(),
+        website_url: String::new(),
+        download_count: 0,
+        latest_version: entry.display_name.clone(),
+    };
+    // the cached hash is passed on so the archive is verified again before it is installed
+    let file = AddonFile {
+        id: entry.file_id,
+        display_name: entry.display_name.clone(),
+        file_name: cached.file_name,
+        download_url: String::new(),
+        sha1: Some(cached.hash),
+        game_versions: Vec::new(),
+        released: String::new(),
+        release_tag: entry.release_tag.clone(),
+    };
+    Some((info, file))
+}
+
+/// Installs a file of an addon, downloading it unless it is already cached, and records
+/// it in the manifest under `mod_id`
+pub async fn install_file(
+    info: &AddonInfo,
+    mod_id: u32,
+    file: &AddonFile,
+    cancel: &CancellationToken,
+) -> Result<ManifestEntry, Box<dyn std::error::Error>> {
+    let archive_path = match download_cache::lookup(info.provider, file) {
+        Some(cached) => {
+            eprintln!("Using cached {}", cached.display());
+            cached
+        }
+        None => download(info.provider, file, cancel).await?,
+    };
+
+    let directories = curseforge_api::install_archive(&archive_path, mod_id, cancel)?;
+
+    let entry = ManifestEntry {
+        mod_id,
+        name: info.name.clone(),
+        file_id: file.id,
+        file_name: file.file_name.clone(),
+        display_name: file.display_name.clone(),
+        directories,
+        installed_at: now_timestamp(),
+        provider: info.provider.to_string(),
+        source_id: info.id.clone(),
+        release_tag: file.release_tag.clone(),
+        replaced: Vec::new(),
+    };
+    let mut manifest = Manifest::load()?;
+    manifest.upsert(entry.clone());
+    manifest.save()?;
+
+    Ok(entry)
+}
+
+/// Downloads a file of a provider into the download cache
+async fn download(
+    provider: &str,
+    file: &AddonFile,
+    cancel: &CancellationToken,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let partial_path = download_cache::partial_path(provider, file)?;
+    let file_path = &partial_path.to_string_lossy().to_string();
+    if let Err(e) = curseforge_api::download_file(&file.download_url, file_path, cancel).await {
+        std::fs::remove_file(file_path).ok();
+        return Err(e);
+    }
+    eprintln!(
+        "Downloaded {} ({} bytes)",
+        file.file_name,
+        std::fs::metadata(file_path)?.len()
+    );
+
+    download_cache::store(provider, file, &partial_path)
+}
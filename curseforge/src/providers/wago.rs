@@ -0,0 +1,272 @@
+use super::{AddonFile, AddonInfo, AddonProvider};
+use crate::config::CurseForgeConfig;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Name of the provider in the manifest and for `get --source`
+pub const NAME: &str = "wago";
+
+const API_URL: &str = "https://addons.wago.io/api/external";
+/// Wago's name for the game flavor Addown manages
+const GAME_VERSION: &str = "retail";
+
+/// Addons from addons.wago.io
+pub struct Wago;
+
+/// How stable a release is. Installing from a channel also accepts more stable releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReleaseChannel {
+    Stable,
+    Beta,
+    Alpha,
+}
+
+impl ReleaseChannel {
+    pub fn parse(channel: &str) -> Option<Self> {
+        match channel.trim().to_lowercase().as_str() {
+            "stable" | "release" => Some(Self::Stable),
+            "beta" => Some(Self::Beta),
+            "alpha" => Some(Self::Alpha),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Alpha => "alpha",
+        }
+    }
+
+    /// The channel set with ADDOWN_WAGO_CHANNEL
+    pub fn configured() -> Result<Self, Box<dyn std::error::Error>> {
+        let channel = &CurseForgeConfig::get().wago_channel;
+        Self::parse(channel).ok_or_else(|| {
+            format!(
+                "Invalid Wago release channel '{}', use stable, beta or alpha",
+                channel
+            )
+            .into()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WagoAddon {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    slug: String,
+    display_name: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    website_url: String,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    download_count: u64,
+    /// Newest release of each channel, keyed by channel name
+    #[serde(default, alias = "releases")]
+    recent_release: HashMap<String, WagoRelease>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WagoRelease {
+    label: String,
+    #[serde(default)]
+    created_at: String,
+    download_link: String,
+    #[serde(default)]
+    supported_retail_patches: Vec<String>,
+}
+
+impl WagoAddon {
+    /// The newest release in `channel` or a more stable one
+    fn release(&self, channel: ReleaseChannel) -> Option<&WagoRelease> {
+        self.recent_release
+            .iter()
+            .filter(|(name, _)| ReleaseChannel::parse(name).is_some_and(|name| name <= channel))
+            .map(|(_, release)| release)
+            .max_by(|a, b| a.created_at.cmp(&b.created_at))
+    }
+
+    fn info(&self, channel: ReleaseChannel) -> AddonInfo {
+        AddonInfo {
+            provider: NAME,
+            id: self.id.clone(),
+            name: self.display_name.clone(),
+            summary: self.summary.clone(),
+            authors: self.authors.clone(),
+            website_url: if self.website_url.is_empty() {
+                format!("https://addons.wago.io/addons/{}", self.slug)
+            } else {
+                self.website_url.clone()
+            },
+            download_count: self.download_count,
+            latest_version: self
+                .release(channel)
+                .map(|release| release.label.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl AddonProvider for Wago {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn search(&self, filter: &str) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let channel = ReleaseChannel::configured()?;
+        let url = format!(
+            "{}/addons/_search?query={}&game_version={}",
+            API_URL,
+            urlencoding::encode(filter),
+            GAME_VERSION
+        );
+        let addons: Vec<WagoAddon> = get(&url).await?;
+        Ok(addons.iter().map(|addon| addon.info(channel)).collect())
+    }
+
+    async fn get_info(&self, id: &str) -> Result<AddonInfo, Box<dyn std::error::Error>> {
+        let channel = ReleaseChannel::configured()?;
+        Ok(get_addon(id).await?.info(channel))
+    }
+
+    /// Wago only reports the newest release of each channel
+    async fn list_files(&self, id: &str) -> Result<Vec<AddonFile>, Box<dyn std::error::Error>> {
+        let addon = get_addon(id).await?;
+        let mut releases: Vec<&WagoRelease> = addon.recent_release.values().collect();
+        releases.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(releases
+            .into_iter()
+            .map(|release| addon_file(&addon, release))
+            .collect())
+    }
+
+    async fn resolve_download(&self, id: &str) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let channel = ReleaseChannel::configured()?;
+        resolve_in_channel(id, channel).await
+    }
+
+    async fn batch_lookup(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let channel = ReleaseChannel::configured()?;
+        let api_key = api_key()?;
+        let url = format!("{}/addons/_recents?game_version={}", API_URL, GAME_VERSION);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .bearer_auth(api_key)
+            .header("accept", "application/json")
+            .json(&serde_json::json!({ "addons": ids }))
+            .send()
+            .await?
+            .error_for_status()?;
+        let response_text = response.text().await?;
+
+        // recents are keyed by addon id, which the entries themselves may leave out
+        let mut value: serde_json::Value = serde_json::from_str(&response_text)?;
+        let recents: HashMap<String, WagoAddon> = serde_json::from_value(value["addons"].take())?;
+        Ok(recents
+            .into_iter()
+            .map(|(id, mut addon)| {
+                if addon.id.is_empty() {
+                    addon.id = id;
+                }
+                addon.info(channel)
+            })
+            .collect())
+    }
+}
+
+/// The newest release of an addon in `channel` or a more stable one
+pub async fn resolve_in_channel(
+    id: &str,
+    channel: ReleaseChannel,
+) -> Result<AddonFile, Box<dyn std::error::Error>> {
+    let addon = get_addon(id).await?;
+    let release = addon.release(channel).ok_or_else(|| {
+        format!(
+            "{} has no release in the {} channel",
+            addon.display_name,
+            channel.name()
+        )
+    })?;
+    Ok(addon_file(&addon, release))
+}
+
+fn api_key() -> Result<&'static str, Box<dyn std::error::Error>> {
+    CurseForgeConfig::get()
+        .wago_api_key
+        .as_deref()
+        .ok_or_else(|| "WAGO_API_KEY must be set in the environment to use Wago".into())
+}
+
+async fn get_addon(id: &str) -> Result<WagoAddon, Box<dyn std::error::Error>> {
+    let url = format!(
+        "{}/addons/{}?game_version={}",
+        API_URL,
+        urlencoding::encode(id.trim()),
+        GAME_VERSION
+    );
+    let mut addon: WagoAddon = get(&url).await?;
+    if addon.id.is_empty() {
+        addon.id = id.trim().to_string();
+    }
+    Ok(addon)
+}
+
+/// Fetches an api endpoint. Responses are sometimes wrapped in a `data` field.
+async fn get<T: DeserializeOwned>(url: &str) -> Result<T, Box<dyn std::error::Error>> {
+    let api_key = api_key()?;
+    let response = reqwest::Client::new()
+        .get(url)
+        .bearer_auth(api_key)
+        .header("accept", "application/json")
+        .send()
+        .await?
+        .error_for_status()?;
+    let response_text = response.text().await?;
+
+    let mut value: serde_json::Value = serde_json::from_str(&response_text)?;
+    if let Some(data) = value.get_mut("data") {
+        value = data.take();
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+fn addon_file(addon: &WagoAddon, release: &WagoRelease) -> AddonFile {
+    let name = if addon.slug.is_empty() {
+        &addon.id
+    } else {
+        &addon.slug
+    };
+    let file_name: String = format!("{}-{}.zip", name, release.label)
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || ".-_".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    AddonFile {
+        id: 0,
+        display_name: release.label.clone(),
+        file_name,
+        download_url: release.download_link.clone(),
+        sha1: None,
+        game_versions: release.supported_retail_patches.clone(),
+        released: release.created_at.clone(),
+        release_tag: None,
+    }
+}
@@ -0,0 +1,275 @@
+use super::{AddonFile, AddonInfo, AddonProvider};
+use crate::manifest::format_timestamp;
+use async_trait::async_trait;
+use serde::{Deserialize, Deserializer};
+use std::sync::{Arc, Mutex};
+
+/// Name of the provider in the manifest and for `get --source`
+pub const NAME: &str = "wowi";
+/// Most results `search` returns
+const MAX_SEARCH_RESULTS: usize = 50;
+
+const API_URL: &str = "https://api.mmoui.com/v3/game/WOW";
+
+/// The file list is several megabytes, so it is only downloaded once per session
+static FILE_LIST: Mutex<Option<Arc<Vec<ListedFile>>>> = Mutex::new(None);
+
+/// Addons from wowinterface.com
+pub struct WowInterface;
+
+/// An addon in the file list
+#[derive(Debug, Clone, Deserialize)]
+struct ListedFile {
+    #[serde(rename = "UID", deserialize_with = "string_or_number")]
+    id: String,
+    #[serde(rename = "UIName")]
+    name: String,
+    #[serde(rename = "UIVersion", default, deserialize_with = "string_or_number")]
+    version: String,
+    #[serde(rename = "UIAuthorName", default)]
+    author: String,
+    #[serde(rename = "UIFileInfoURL", default)]
+    info_url: String,
+    #[serde(
+        rename = "UIDownloadTotal",
+        default,
+        deserialize_with = "string_or_number"
+    )]
+    download_total: String,
+    /// Game versions the author marked the file compatible with, missing for older files
+    #[serde(rename = "UICompatibility", default)]
+    compatibility: Option<Vec<Compatibility>>,
+}
+
+/// A game version an addon works with, e.g. `11.0.2` named "The War Within (11.0.2)"
+#[derive(Debug, Clone, Deserialize)]
+struct Compatibility {
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    name: String,
+}
+
+/// Details of an addon and its current file
+#[derive(Debug, Clone, Deserialize)]
+struct FileDetails {
+    #[serde(rename = "UID", deserialize_with = "string_or_number")]
+    id: String,
+    #[serde(rename = "UIName")]
+    name: String,
+    #[serde(rename = "UIVersion", default, deserialize_with = "string_or_number")]
+    version: String,
+    /// Milliseconds since the epoch
+    #[serde(rename = "UIDate", default)]
+    date: u64,
+    #[serde(rename = "UIFileName")]
+    file_name: String,
+    #[serde(rename = "UIDownload")]
+    download_url: String,
+    #[serde(rename = "UIAuthorName", default)]
+    author: String,
+    #[serde(rename = "UIDescription", default)]
+    description: String,
+    #[serde(rename = "UIHitCount", default, deserialize_with = "string_or_number")]
+    hit_count: String,
+}
+
+/// The api sends most numbers as strings, but not always
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(value) => value,
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    })
+}
+
+#[async_trait]
+impl AddonProvider for WowInterface {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn search(&self, filter: &str) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let filter = filter.to_lowercase();
+        let files = file_list().await?;
+        let mut matches: Vec<&ListedFile> = files
+            .iter()
+            .filter(|file| file.name.to_lowercase().contains(&filter))
+            .collect();
+        matches.sort_by_key(|file| std::cmp::Reverse(parse_count(&file.download_total)));
+        matches.truncate(MAX_SEARCH_RESULTS);
+
+        Ok(matches.into_iter().map(listed_info).collect())
+    }
+
+    async fn get_info(&self, id: &str) -> Result<AddonInfo, Box<dyn std::error::Error>> {
+        let details = file_details(id).await?;
+        Ok(AddonInfo {
+            provider: NAME,
+            id: details.id.clone(),
+            name: details.name.clone(),
+            summary: details
+                .description
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string(),
+            authors: vec![details.author.clone()],
+            website_url: info_url(&details.id),
+            download_count: parse_count(&details.hit_count),
+            latest_version: details.version.clone(),
+        })
+    }
+
+    /// WoWInterface only serves the current file of an addon
+    async fn list_files(&self, id: &str) -> Result<Vec<AddonFile>, Box<dyn std::error::Error>> {
+        Ok(vec![self.resolve_download(id).await?])
+    }
+
+    /// The api has no sha1 hashes, so downloads can't be verified
+    async fn resolve_download(&self, id: &str) -> Result<AddonFile, Box<dyn std::error::Error>> {
+        let files = file_list().await?;
+        let listed = files
+            .iter()
+            .find(|file| file.id == id.trim())
+            .ok_or_else(|| format!("WoWInterface addon {} not found", id.trim()))?;
+        let compatibility = listed.compatibility.as_deref().unwrap_or_default();
+        if !compatibility.is_empty() && compatibility.iter().all(is_classic) {
+            return Err(format!(
+                "{} is only available for WoW Classic on WoWInterface",
+                listed.name
+            )
+            .into());
+        }
+
+        let details = file_details(id).await?;
+        Ok(AddonFile {
+            id: file_version(details.date),
+            display_name: details.version,
+            file_name: details.file_name,
+            download_url: details.download_url,
+            sha1: None,
+            game_versions: compatibility
+                .iter()
+                .filter(|version| !is_classic(version))
+                .map(|version| version.version.clone())
+                .collect(),
+            released: format_timestamp(details.date / 1000),
+            release_tag: None,
+        })
+    }
+
+    async fn batch_lookup(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<AddonInfo>, Box<dyn std::error::Error>> {
+        let files = file_list().await?;
+        Ok(files
+            .iter()
+            .filter(|file| ids.iter().any(|id| id.trim() == file.id))
+            .map(listed_info)
+            .collect())
+    }
+}
+
+/// Every addon on WoWInterface, downloaded on first use
+async fn file_list() -> Result<Arc<Vec<ListedFile>>, Box<dyn std::error::Error>> {
+    if let Some(files) = FILE_LIST.lock().unwrap().as_ref() {
+        return Ok(files.clone());
+    }
+
+    let response = reqwest::get(format!("{}/filelist.json", API_URL))
+        .await?
+        .error_for_status()?;
+    let response_text = response.text().await?;
+    let files: Arc<Vec<ListedFile>> = Arc::new(serde_json::from_str(&response_text)?);
+    *FILE_LIST.lock().unwrap() = Some(files.clone());
+
+    Ok(files)
+}
+
+async fn file_details(id: &str) -> Result<FileDetails, Box<dyn std::error::Error>> {
+    let id = id.trim();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid WoWInterface id: {}", id).into());
+    }
+
+    let response = reqwest::get(format!("{}/filedetails/{}.json", API_URL, id))
+        .await?
+        .error_for_status()?;
+    let response_text = response.text().await?;
+    let details: Vec<FileDetails> = serde_json::from_str(&response_text)?;
+    details
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("WoWInterface addon {} not found", id).into())
+}
+
+fn listed_info(file: &ListedFile) -> AddonInfo {
+    AddonInfo {
+        provider: NAME,
+        id: file.id.clone(),
+        name: file.name.clone(),
+        summary: String::new(),
+        authors: vec![file.author.clone()],
+        website_url: if file.info_url.is_empty() {
+            info_url(&file.id)
+        } else {
+            file.info_url.clone()
+        },
+        download_count: parse_count(&file.download_total),
+        latest_version: file.version.clone(),
+    }
+}
+
+/// WoWInterface doesn't number the files of an addon, so its upload time in seconds tells
+/// them apart for updates and the download cache
+fn file_version(date_ms: u64) -> u32 {
+    u32::try_from(date_ms / 1000).unwrap_or(0)
+}
+
+/// Whether a game version is one of the Classic flavors rather than retail, which Addown manages
+fn is_classic(version: &Compatibility) -> bool {
+    version.name.to_lowercase().contains("classic")
+}
+
+fn info_url(id: &str) -> String {
+    format!("https://www.wowinterface.com/downloads/info{}", id)
+}
+
+fn parse_count(count: &str) -> u64 {
+    count.trim().parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_list_reads_compatibility() {
+        let files: Vec<ListedFile> = serde_json::from_str(
+            r#"[
+                {"UID": "5108", "UIName": "Both", "UIVersion": 2.1, "UICompatibility": [
+                    {"version": "11.0.2", "name": "The War Within (11.0.2)"},
+                    {"version": "1.15.3", "name": "WoW Classic (1.15.3)"}
+                ]},
+                {"UID": 24910, "UIName": "Old", "UICompatibility": null}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(files[0].version, "2.1");
+        let compatibility = files[0].compatibility.as_deref().unwrap();
+        assert!(!is_classic(&compatibility[0]));
+        assert!(is_classic(&compatibility[1]));
+        assert_eq!(files[1].id, "24910");
+        assert!(files[1].compatibility.is_none());
+    }
+
+    #[test]
+    fn file_version_is_the_upload_time_in_seconds() {
+        assert_eq!(file_version(1_721_000_000_123), 1_721_000_000);
+        assert_eq!(file_version(u64::MAX), 0);
+    }
+}
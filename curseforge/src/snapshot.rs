@@ -0,0 +1,257 @@
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::curseforge_api;
+use crate::installed_mods::read_toc_version;
+use crate::manifest::{now_timestamp, Manifest};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const INDEX_FILE: &str = "snapshot.json";
+
+/// Summary stored inside every snapshot so they can be listed and diffed without extracting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotIndex {
+    pub name: String,
+    pub created_at: u64,
+    /// Every folder in AddOns and the version its toc reports
+    pub folders: BTreeMap<String, String>,
+}
+
+pub struct Snapshot {
+    pub index: SnapshotIndex,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// One difference between two snapshots
+pub enum SnapshotChange {
+    Added {
+        name: String,
+        version: String,
+    },
+    Removed {
+        name: String,
+        version: String,
+    },
+    Changed {
+        name: String,
+        from: String,
+        to: String,
+    },
+}
+
+/// Archive of a snapshot. Names are checked so they can't reach outside the snapshots dir.
+fn snapshot_path(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(format!("Invalid snapshot name '{}'", name).into());
+    }
+    Ok(Path::new(&CurseForgeConfig::get().snapshots_path()).join(format!("{}.zip", name)))
+}
+
+/// The Interface folder that contains AddOns
+fn interface_path() -> PathBuf {
+    let addons_path = CurseForgeConfig::get().addons_path();
+    Path::new(&addons_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(&addons_path))
+}
+
+/// Archives the whole AddOns folder and the manifest under `name`
+pub fn create_snapshot(name: Option<String>) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    let created_at = now_timestamp();
+    let name = name.unwrap_or_else(|| chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+    let path = snapshot_path(&name)?;
+    if path.exists() {
+        return Err(format!("Snapshot '{}' already exists", name).into());
+    }
+
+    let addons_path = PathBuf::from(CurseForgeConfig::get().addons_path());
+    let mut folders = BTreeMap::new();
+    for entry in fs::read_dir(&addons_path)? {
+        let entry = entry?;
+        let folder = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && !folder.starts_with('.') {
+            folders.insert(folder, read_toc_version(&entry.path()).unwrap_or_default());
+        }
+    }
+
+    let index = SnapshotIndex {
+        name,
+        created_at,
+        folders,
+    };
+    let index_json = serde_json::to_vec_pretty(&index)?;
+    let manifest_json = serde_json::to_vec_pretty(&Manifest::load()?)?;
+    let addons_folder = addons_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "AddOns".to_string());
+    curseforge_api::zip_dirs(
+        &path,
+        &interface_path(),
+        &[addons_folder],
+        &[
+            (INDEX_FILE, index_json.as_slice()),
+            (MANIFEST_FILE, manifest_json.as_slice()),
+        ],
+    )?;
+
+    let size = fs::metadata(&path)?.len();
+    Ok(Snapshot { index, path, size })
+}
+
+/// Lists saved snapshots, newest first
+pub fn list_snapshots() -> Result<Vec<Snapshot>, Box<dyn std::error::Error>> {
+    let mut snapshots = Vec::new();
+    let files = match fs::read_dir(CurseForgeConfig::get().snapshots_path()) {
+        Ok(files) => files,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(snapshots),
+        Err(e) => return Err(e.into()),
+    };
+
+    for file in files {
+        let path = file?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("zip") {
+            continue;
+        }
+        if let Ok(index) = read_json::<SnapshotIndex>(&path, INDEX_FILE) {
+            let size = fs::metadata(&path)?.len();
+            snapshots.push(Snapshot { index, path, size });
+        }
+    }
+
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.index.created_at));
+    Ok(snapshots)
+}
+
+/// Replaces the AddOns folder and the manifest with the contents of a snapshot.
+/// The current AddOns folder is first saved as a `pre-restore-` snapshot so the restore can be
+/// undone, and the snapshot is fully extracted before anything in AddOns is touched.
+pub fn restore_snapshot(
+    name: &str,
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = snapshot_path(name)?;
+    if !path.is_file() {
+        return Err(format!("Snapshot '{}' not found", name).into());
+    }
+    let manifest: Manifest = read_json(&path, MANIFEST_FILE)?;
+
+    let addons_path = PathBuf::from(CurseForgeConfig::get().addons_path());
+    let addons_folder = addons_path
+        .file_name()
+        .ok_or("Invalid AddOns path")?
+        .to_os_string();
+    if addons_path.exists() {
+        let safety = create_snapshot(Some(format!(
+            "pre-restore-{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        )))?;
+        eprintln!(
+            "Saved the current AddOns as snapshot '{}'",
+            safety.index.name
+        );
+    }
+
+    let staging_path = interface_path().join(".addown-snapshot-staging");
+    fs::remove_dir_all(&staging_path).ok();
+    let staged = curseforge_api::unzip_file(
+        &path.to_string_lossy(),
+        &staging_path.to_string_lossy(),
+        cancel,
+    );
+    if let Err(e) = staged {
+        fs::remove_dir_all(&staging_path).ok();
+        return Err(e);
+    }
+
+    // past this point the restore is committed and is not interrupted
+    let previous_path = interface_path().join(".addown-snapshot-previous");
+    fs::remove_dir_all(&previous_path).ok();
+    if addons_path.exists() {
+        fs::rename(&addons_path, &previous_path)?;
+    }
+    if let Err(e) = fs::rename(staging_path.join(&addons_folder), &addons_path) {
+        // put the original folder back rather than leaving AddOns missing
+        fs::rename(&previous_path, &addons_path).ok();
+        fs::remove_dir_all(&staging_path).ok();
+        return Err(e.into());
+    }
+    fs::remove_dir_all(&previous_path).ok();
+    fs::remove_dir_all(&staging_path).ok();
+    manifest.save()?;
+
+    Ok(())
+}
+
+/// Lists the addons that were added, removed or changed version between snapshots `a` and `b`.
+/// Addons in the manifest are compared by mod, anything else by AddOns folder.
+pub fn diff_snapshots(a: &str, b: &str) -> Result<Vec<SnapshotChange>, Box<dyn std::error::Error>> {
+    let (a, b) = (snapshot_path(a)?, snapshot_path(b)?);
+    let before = addon_versions(&a)?;
+    let after = addon_versions(&b)?;
+
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = Vec::new();
+    for name in names {
+        match (before.get(name), after.get(name)) {
+            (None, Some(version)) => changes.push(SnapshotChange::Added {
+                name: name.clone(),
+                version: version.clone(),
+            }),
+            (Some(version), None) => changes.push(SnapshotChange::Removed {
+                name: name.clone(),
+                version: version.clone(),
+            }),
+            (Some(from), Some(to)) if from != to => changes.push(SnapshotChange::Changed {
+                name: name.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Maps addon name to version for everything in a snapshot
+fn addon_versions(path: &Path) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    if !path.is_file() {
+        return Err(format!("Snapshot '{}' not found", path.display()).into());
+    }
+    let index: SnapshotIndex = read_json(path, INDEX_FILE)?;
+    let manifest: Manifest = read_json(path, MANIFEST_FILE)?;
+
+    let mut versions = BTreeMap::new();
+    let mut tracked_folders = BTreeSet::new();
+    for entry in &manifest.addons {
+        versions.insert(entry.name.clone(), entry.display_name.clone());
+        tracked_folders.extend(entry.directories.iter().cloned());
+    }
+    for (folder, version) in index.folders {
+        if !tracked_folders.contains(&folder) {
+            versions.insert(folder, version);
+        }
+    }
+
+    Ok(versions)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(
+    zip_path: &Path,
+    name: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let mut archive = ZipArchive::new(File::open(zip_path)?)?;
+    let mut content = String::new();
+    archive.by_name(name)?.read_to_string(&mut content)?;
+    Ok(serde_json::from_str(&content)?)
+}
@@ -0,0 +1,31 @@
+//! Fixtures shared by the unit tests
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An empty folder under the system temp dir, removed again when it is dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Names include the process id, so each test passes its own `name` to run in parallel
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("addown-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&path).ok();
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
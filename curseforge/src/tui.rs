@@ -0,0 +1,682 @@
+use crate::cancellation::CancellationToken;
+use crate::installed_mods::InstalledAddon;
+use crate::models::Mod;
+use crate::providers::curseforge::WOW_GAME_ID;
+use crate::providers::{AddonInfo, DEFAULT_PROVIDER};
+use crate::{backup, curseforge_api, game_version, html, installed_mods, providers};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::JoinHandle;
+
+const KEY_HELP: &str =
+    "Tab switch  / filter  Enter details  u update  d delete  r rollback  i install  q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Installed,
+    Search,
+}
+
+/// What typed characters go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputMode {
+    Normal,
+    /// Narrowing the installed list
+    Filter,
+    /// Typing a CurseForge search
+    Query,
+}
+
+/// An action that changes AddOns, run from the key loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Install(u32),
+    Update(u32),
+    Delete(u32),
+    Rollback(u32),
+}
+
+/// Work a key press asked for that has to wait for the network or the disk
+enum Pending {
+    Search,
+    Details(u32),
+    Action(Action),
+}
+
+/// Everything fetched for the detail pane of one mod
+struct Details {
+    game_mod: Mod,
+    latest_file: Option<String>,
+    changelog: String,
+}
+
+struct App {
+    tab: Tab,
+    input_mode: InputMode,
+    installed: Vec<InstalledAddon>,
+    filter: String,
+    installed_state: ListState,
+    query: String,
+    results: Vec<Mod>,
+    results_state: ListState,
+    details: HashMap<u32, Details>,
+    /// Details of installed addons from providers other than CurseForge
+    addon_details: HashMap<u32, AddonInfo>,
+    detail_scroll: u16,
+    /// A delete waiting for the user to press `y`
+    confirm: Option<Action>,
+    status: String,
+    quit: bool,
+}
+
+/// Runs the full screen interface until the user quits
+pub async fn run(cancel: &CancellationToken) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Scanning installed addons...");
+    let installed = installed_mods::scan_installed_mods().await?;
+    let mut app = App::new(installed);
+
+    let mut terminal = ratatui::init();
+    let (mut events, reader) = spawn_event_reader();
+    // kept as text, a boxed error would stop this future from being Send across the await below
+    let result = app
+        .run(&mut terminal, &mut events, cancel)
+        .await
+        .map_err(|e| e.to_string());
+    // the reader stops once the receiver is gone, and must before the shell gets the terminal
+    drop(events);
+    reader.await.ok();
+    ratatui::restore();
+
+    Ok(result?)
+}
+
+/// Reads terminal events on a blocking thread so waiting for keys never holds up a tokio
+/// worker. The thread stops soon after the receiver is dropped.
+fn spawn_event_reader() -> (UnboundedReceiver<io::Result<Event>>, JoinHandle<()>) {
+    let (events_tx, events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let reader = tokio::task::spawn_blocking(move || {
+        while !events_tx.is_closed() {
+            match event::poll(Duration::from_millis(100)) {
+                Ok(false) => {}
+                Ok(true) => {
+                    if events_tx.send(event::read()).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    events_tx.send(Err(e)).ok();
+                    break;
+                }
+            }
+        }
+    });
+    (events_rx, reader)
+}
+
+impl App {
+    fn new(installed: Vec<InstalledAddon>) -> Self {
+        let mut installed_state = ListState::default();
+        if !installed.is_empty() {
+            installed_state.select(Some(0));
+        }
+
+        Self {
+            tab: Tab::Installed,
+            input_mode: InputMode::Normal,
+            installed,
+            filter: String::new(),
+            installed_state,
+            query: String::new(),
+            results: Vec::new(),
+            results_state: ListState::default(),
+            details: HashMap::new(),
+            addon_details: HashMap::new(),
+            detail_scroll: 0,
+            confirm: None,
+            status: String::from(KEY_HELP),
+            quit: false,
+        }
+    }
+
+    async fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        events: &mut UnboundedReceiver<io::Result<Event>>,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        while !self.quit && !cancel.is_cancelled() {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = cancel.cancelled() => break,
+            };
+            let Some(event) = event else {
+                break;
+            };
+            let Event::Key(key) = event? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let action = match self.input_mode {
+                InputMode::Normal => self.handle_key(key),
+                InputMode::Filter | InputMode::Query => self.handle_input_key(key),
+            };
+            match action {
+                Some(Pending::Search) => {
+                    self.status = format!("Searching for {}...", self.query);
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.search().await;
+                }
+                Some(Pending::Details(mod_id)) => {
+                    self.status = String::from("Loading details...");
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.load_details(mod_id).await;
+                }
+                Some(Pending::Action(action)) => {
+                    self.status = self.describe(action);
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.perform(action, cancel).await;
+                    // installs print progress to stderr, repaint everything over it
+                    terminal.clear()?;
+                }
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles a key while browsing. Returns work that needs the network or the disk.
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Pending> {
+        if let Some(action) = self.confirm.take() {
+            if key.code == KeyCode::Char('y') {
+                return Some(Pending::Action(action));
+            }
+            self.status = String::from("Cancelled");
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.tab = match self.tab {
+                    Tab::Installed => Tab::Search,
+                    Tab::Search => Tab::Installed,
+                };
+                self.detail_scroll = 0;
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX),
+            KeyCode::Char('J') => self.detail_scroll = self.detail_scroll.saturating_add(1),
+            KeyCode::Char('K') => self.detail_scroll = self.detail_scroll.saturating_sub(1),
+            KeyCode::Char('/') => {
+                self.input_mode = match self.tab {
+                    Tab::Installed => InputMode::Filter,
+                    Tab::Search => InputMode::Query,
+                };
+            }
+            KeyCode::Enter => return self.selected_id().map(Pending::Details),
+            KeyCode::Char('i') if self.tab == Tab::Search => {
+                return self
+                    .selected_id()
+                    .map(|id| Pending::Action(Action::Install(id)))
+            }
+            KeyCode::Char('u') if self.tab == Tab::Installed => {
+                return self
+                    .selected_id()
+                    .map(|id| Pending::Action(Action::Update(id)))
+            }
+            KeyCode::Char('r') if self.tab == Tab::Installed => {
+                return self
+                    .selected_id()
+                    .map(|id| Pending::Action(Action::Rollback(id)))
+            }
+            KeyCode::Char('d') if self.tab == Tab::Installed => {
+                if let Some(id) = self.selected_id() {
+                    self.status = format!("Delete {}? Press y to confirm", self.name_of(id));
+                    self.confirm = Some(Action::Delete(id));
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Handles a key while typing a filter or search
+    fn handle_input_key(&mut self, key: KeyEvent) -> Option<Pending> {
+        let text = match self.input_mode {
+            InputMode::Query => &mut self.query,
+            _ => &mut self.filter,
+        };
+        match key.code {
+            KeyCode::Char(ch) => text.push(ch),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Esc => {
+                text.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Enter => {
+                let searching = self.input_mode == InputMode::Query && !self.query.is_empty();
+                self.input_mode = InputMode::Normal;
+                if searching {
+                    return Some(Pending::Search);
+                }
+            }
+            _ => {}
+        }
+
+        if self.input_mode == InputMode::Filter || key.code == KeyCode::Esc {
+            // the filtered list changed under the selection
+            let selected = if self.visible_installed().is_empty() {
+                None
+            } else {
+                Some(0)
+            };
+            self.installed_state.select(selected);
+        }
+
+        None
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        let len = match self.tab {
+            Tab::Installed => self.visible_installed().len(),
+            Tab::Search => self.results.len(),
+        };
+        let state = match self.tab {
+            Tab::Installed => &mut self.installed_state,
+            Tab::Search => &mut self.results_state,
+        };
+        if len == 0 {
+            state.select(None);
+            return;
+        }
+
+        let current = state.selected().unwrap_or(0) as isize;
+        let next = current.saturating_add(by).clamp(0, len as isize - 1);
+        state.select(Some(next as usize));
+        self.detail_scroll = 0;
+    }
+
+    fn visible_installed(&self) -> Vec<&InstalledAddon> {
+        let filter = self.filter.to_lowercase();
+        self.installed
+            .iter()
+            .filter(|addon| {
+                filter.is_empty()
+                    || addon.name.to_lowercase().contains(&filter)
+                    || addon.folder.to_lowercase().contains(&filter)
+            })
+            .collect()
+    }
+
+    /// The CurseForge id of the selected addon or search result
+    fn selected_id(&self) -> Option<u32> {
+        let id = match self.tab {
+            Tab::Installed => {
+                let index = self.installed_state.selected()?;
+                self.visible_installed().get(index)?.id
+            }
+            Tab::Search => self.results.get(self.results_state.selected()?)?.id,
+        };
+        (id != 0).then_some(id)
+    }
+
+    fn name_of(&self, mod_id: u32) -> String {
+        self.installed
+            .iter()
+            .find(|addon| addon.id == mod_id)
+            .map(|addon| addon.name.clone())
+            .or_else(|| {
+                self.results
+                    .iter()
+                    .find(|game_mod| game_mod.id == mod_id)
+                    .map(|game_mod| game_mod.name.clone())
+            })
+            .unwrap_or_else(|| mod_id.to_string())
+    }
+
+    fn describe(&self, action: Action) -> String {
+        match action {
+            Action::Install(id) => format!("Installing {}...", self.name_of(id)),
+            Action::Update(id) => format!("Updating {}...", self.name_of(id)),
+            Action::Delete(id) => format!("Deleting {}...", self.name_of(id)),
+            Action::Rollback(id) => format!("Rolling back {}...", self.name_of(id)),
+        }
+    }
+
+    async fn search(&mut self) {
+        match curseforge_api::search_mods(WOW_GAME_ID, &self.query).await {
+            Ok(results) => {
+                self.status = format!("{} results for {}", results.len(), self.query);
+                self.results = results;
+                let selected = (!self.results.is_empty()).then_some(0);
+                self.results_state.select(selected);
+            }
+            Err(e) => self.status = format!("Search failed: {}", e),
+        }
+    }
+
+    /// Fetches the mod and the changelog of its file for the current game version. Addons from
+    /// other providers are looked up there, by the id they have at that provider.
+    async fn load_details(&mut self, mod_id: u32) {
+        let other_provider = self
+            .installed
+            .iter()
+            .find(|addon| addon.id == mod_id && addon.provider != DEFAULT_PROVIDER)
+            .map(|addon| (addon.provider.clone(), addon.source_id.clone()));
+        if let Some((provider, source_id)) = other_provider {
+            self.load_addon_details(mod_id, &provider, &source_id).await;
+            return;
+        }
+
+        let game_mod = match curseforge_api::get_mod_info(mod_id).await {
+            Ok(game_mod) => game_mod,
+            Err(e) => {
+                self.status = format!("Could not load details: {}", e);
+                return;
+            }
+        };
+        let latest = game_version::get_mod_file_for_game_version(&game_mod)
+            .ok()
+            .map(|file| (file.id, file.display_name.clone()));
+
+        let mut changelog = String::new();
+        if let Some((file_id, _)) = &latest {
+            changelog = match curseforge_api::get_mod_file_changelog(mod_id, *file_id).await {
+                Ok(changelog) => html::to_text(&changelog),
+                Err(e) => format!("Could not load the changelog: {}", e),
+            };
+        }
+
+        self.details.insert(
+            mod_id,
+            Details {
+                game_mod,
+                latest_file: latest.map(|(_, name)| name),
+                changelog,
+            },
+        );
+        self.detail_scroll = 0;
+        self.status = String::from(KEY_HELP);
+    }
+
+    async fn load_addon_details(&mut self, mod_id: u32, provider: &str, source_id: &str) {
+        if !providers::has_remote(provider) {
+            self.status =
+                String::from("Addons installed from local files or linked have no details to load");
+            return;
+        }
+        let provider = match providers::provider(provider) {
+            Ok(provider) => provider,
+            Err(e) => {
+                self.status = format!("Could not load details: {}", e);
+                return;
+            }
+        };
+        match provider.get_info(source_id).await {
+            Ok(info) => {
+                self.addon_details.insert(mod_id, info);
+                self.detail_scroll = 0;
+                self.status = String::from(KEY_HELP);
+            }
+            Err(e) => self.status = format!("Could not load details: {}", e),
+        }
+    }
+
+    async fn perform(&mut self, action: Action, cancel: &CancellationToken) {
+        let result = match action {
+            Action::Install(id) | Action::Update(id) => install_latest(id, cancel).await,
+            Action::Delete(id) => curseforge_api::uninstall_mod(id)
+                .map(|entry| format!("Deleted {}", entry.name))
+                .map_err(|e| e.to_string()),
+            Action::Rollback(id) => backup::rollback(id, None, cancel)
+                .map(|entry| format!("Rolled back {} to {}", entry.name, entry.display_name))
+                .map_err(|e| e.to_string()),
+        };
+        self.status = match result {
+            Ok(message) => message,
+            Err(e) => format!("Failed: {}", e),
+        };
+
+        match installed_mods::scan_installed_mods().await {
+            Ok(installed) => {
+                self.installed = installed;
+                let selected = self
+                    .installed_state
+                    .selected()
+                    .map(|index| index.min(self.visible_installed().len().saturating_sub(1)));
+                self.installed_state.select(selected);
+            }
+            Err(e) => self.status = format!("Could not rescan AddOns: {}", e),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs_area, main_area, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main_area);
+
+        let selected_tab = match self.tab {
+            Tab::Installed => 0,
+            Tab::Search => 1,
+        };
+        frame.render_widget(
+            Tabs::new(vec!["Installed", "Search"])
+                .select(selected_tab)
+                .highlight_style(Style::new().bold().green()),
+            tabs_area,
+        );
+
+        self.draw_list(frame, list_area);
+        frame.render_widget(
+            Paragraph::new(self.detail_text())
+                .wrap(Wrap { trim: false })
+                .scroll((self.detail_scroll, 0))
+                .block(Block::bordered().title("Details")),
+            detail_area,
+        );
+
+        let status = match self.input_mode {
+            InputMode::Filter => format!("Filter: {}", self.filter),
+            InputMode::Query => format!("Search CurseForge: {}", self.query),
+            InputMode::Normal => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let (title, items): (String, Vec<ListItem>) = match self.tab {
+            Tab::Installed => {
+                let visible = self.visible_installed();
+                let title = if self.filter.is_empty() {
+                    format!("Installed ({})", visible.len())
+                } else {
+                    format!("Installed ({}, filter: {})", visible.len(), self.filter)
+                };
+                let items = visible
+                    .iter()
+                    .map(|addon| {
+                        let line = Line::from(format!("{}  {}", addon.name, addon.version));
+                        if addon.id == 0 {
+                            ListItem::new(line.dark_gray())
+                        } else {
+                            ListItem::new(line)
+                        }
+                    })
+                    .collect();
+                (title, items)
+            }
+            Tab::Search => (
+                format!("Search results ({})", self.results.len()),
+                self.results
+                    .iter()
+                    .map(|game_mod| ListItem::new(game_mod.name.clone()))
+                    .collect(),
+            ),
+        };
+
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().reversed())
+            .highlight_symbol("> ");
+        let state = match self.tab {
+            Tab::Installed => &mut self.installed_state,
+            Tab::Search => &mut self.results_state,
+        };
+        frame.render_stateful_widget(list, area, state);
+    }
+
+    fn detail_text(&self) -> Text<'static> {
+        let mut lines: Vec<Line> = Vec::new();
+        let installed = match self.tab {
+            Tab::Installed => self
+                .installed_state
+                .selected()
+                .and_then(|index| self.visible_installed().get(index).copied()),
+            Tab::Search => None,
+        };
+        if let Some(addon) = installed {
+            lines.push(Line::from(addon.name.clone()).bold());
+            lines.push(Line::from(format!("Folder: {}", addon.folder)));
+            lines.push(Line::from(format!("Installed version: {}", addon.version)));
+            if !addon.notes.is_empty() {
+                lines.push(Line::from(addon.notes.clone()));
+            }
+            if addon.id == 0 {
+                lines.push(Line::from(""));
+                lines.push(Line::from("Not matched to a CurseForge project").dark_gray());
+                return Text::from(lines);
+            }
+            if addon.provider != DEFAULT_PROVIDER {
+                lines.push(Line::from(format!(
+                    "Source: {} {}",
+                    addon.provider, addon.source_id
+                )));
+                lines.push(Line::from(""));
+                match self.addon_details.get(&addon.id) {
+                    Some(info) => addon_info_lines(info, &mut lines),
+                    None if providers::has_remote(&addon.provider) => {
+                        lines.push(Line::from("Press Enter to load details").dark_gray())
+                    }
+                    None => {}
+                }
+                return Text::from(lines);
+            }
+            lines.push(Line::from(""));
+        }
+
+        let Some(mod_id) = self.selected_id() else {
+            return Text::from(lines);
+        };
+        let loaded = self.details.get(&mod_id);
+        let game_mod = loaded.map(|details| &details.game_mod).or_else(|| {
+            self.results
+                .iter()
+                .find(|game_mod| game_mod.id == mod_id)
+                .filter(|_| self.tab == Tab::Search)
+        });
+        let Some(game_mod) = game_mod else {
+            lines.push(Line::from("Press Enter to load details").dark_gray());
+            return Text::from(lines);
+        };
+
+        if installed.is_none() {
+            lines.push(Line::from(game_mod.name.clone()).bold());
+        }
+        lines.push(Line::from(game_mod.summary.clone()));
+        lines.push(Line::from(""));
+        let authors: Vec<&str> = game_mod
+            .authors
+            .iter()
+            .map(|author| author.name.as_str())
+            .collect();
+        lines.push(Line::from(format!("Authors: {}", authors.join(", "))));
+        lines.push(Line::from(format!(
+            "Downloads: {}",
+            game_mod.download_count
+        )));
+        let links = [
+            ("Website", &game_mod.links.website_url),
+            ("Source", &game_mod.links.source_url),
+            ("Issues", &game_mod.links.issues_url),
+            ("Wiki", &game_mod.links.wiki_url),
+        ];
+        for (label, url) in links {
+            if let Some(url) = url.as_ref().filter(|url| !url.is_empty()) {
+                lines.push(Line::from(format!("{}: {}", label, url)));
+            }
+        }
+
+        match loaded {
+            Some(details) => {
+                if let Some(latest_file) = &details.latest_file {
+                    lines.push(Line::from(format!("Latest file: {}", latest_file)));
+                }
+                lines.push(Line::from(""));
+                lines.push(Line::from("Changelog").bold());
+                for line in details.changelog.lines() {
+                    lines.push(Line::from(line.to_string()));
+                }
+            }
+            None => {
+                lines.push(Line::from(""));
+                lines.push(Line::from("Press Enter to load the changelog").dark_gray());
+            }
+        }
+
+        Text::from(lines)
+    }
+}
+
+/// Lines for the details a provider other than CurseForge has of an addon
+fn addon_info_lines(info: &AddonInfo, lines: &mut Vec<Line<'static>>) {
+    lines.push(Line::from(info.summary.clone()));
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!("Authors: {}", info.authors.join(", "))));
+    if info.download_count > 0 {
+        lines.push(Line::from(format!("Downloads: {}", info.download_count)));
+    }
+    if !info.website_url.is_empty() {
+        lines.push(Line::from(format!("Website: {}", info.website_url)));
+    }
+    if !info.latest_version.is_empty() {
+        lines.push(Line::from(format!("Latest file: {}", info.latest_version)));
+    }
+}
+
+/// Installs the file for the current game version, replacing any installed version
+async fn install_latest(mod_id: u32, cancel: &CancellationToken) -> Result<String, String> {
+    let (provider, source_id) = providers::for_installed(mod_id).map_err(|e| e.to_string())?;
+    let entry = providers::install(provider.as_ref(), &source_id, cancel)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(format!("Installed {} {}", entry.name, entry.display_name))
+}
@@ -0,0 +1,349 @@
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::curseforge_api;
+use crate::game_version;
+use crate::groups;
+use crate::html;
+use crate::installed_mods;
+use crate::manifest::{Manifest, ManifestEntry};
+use crate::models::{Mod, ModFile};
+use crate::providers::{self, curseforge, AddonFile, AddonInfo, DEFAULT_PROVIDER};
+use serde::Serialize;
+use std::path::Path;
+
+/// Most file changelogs shown for one addon, older files are only counted
+const MAX_CHANGELOG_FILES: usize = 10;
+
+/// What `update` will do for one addon
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdatePlanEntry {
+    pub id: u32,
+    pub name: String,
+    pub installed_file_id: u32,
+    pub installed_version: String,
+    pub target_file_id: u32,
+    pub target_version: String,
+    pub needs_update: bool,
+    /// Version the installed files report in their changelog or toc
+    pub local_version: String,
+    /// Changelogs of the files between the installed and target versions, newest first
+    pub changelog: String,
+    /// Why the addon could not be checked, it is left as it is when set
+    pub error: String,
+}
+
+/// A plan entry together with the file it was resolved to, ready to install. Addons that
+/// could not be checked have nothing to install.
+pub struct PlannedUpdate {
+    pub entry: UpdatePlanEntry,
+    install: Option<(AddonInfo, AddonFile)>,
+}
+
+/// Works out which installed addons have a newer file for the current game version.
+/// `ids` limits the plan to some mods, which must all be in the manifest, otherwise every addon
+/// in the manifest is checked. Addons installed from local files and dev links are skipped.
+/// With `force` every addon is reinstalled even if it is up to date. An addon that can't be
+/// checked is kept in the plan with its error, and the rest are still checked.
+pub async fn plan_updates(
+    ids: Option<&[u32]>,
+    force: bool,
+    cancel: &CancellationToken,
+) -> Result<Vec<PlannedUpdate>, Box<dyn std::error::Error>> {
+    let manifest = Manifest::load()?;
+    if let Some(id) = ids
+        .unwrap_or_default()
+        .iter()
+        .find(|id| manifest.find(**id).is_none())
+    {
+        return Err(format!("Addon {} was not installed by Addown", id).into());
+    }
+    let targets: Vec<(u32, Option<ManifestEntry>)> = match ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| (*id, manifest.find(*id).cloned()))
+            .collect(),
+        None => manifest
+            .addons
+            .iter()
+            .map(|entry| (entry.mod_id, Some(entry.clone())))
+            .collect(),
+    };
+
+    let mut plan = Vec::new();
+    let mut failed = 0;
+    for (mod_id, installed) in targets {
+        cancel.check()?;
+        if let Some(entry) = installed
+            .as_ref()
+            .filter(|entry| !providers::has_remote(&entry.provider))
+        {
+            if ids.is_some() {
+                eprintln!(
+                    "Skipping {}, it was installed from {}",
+                    entry.name, entry.file_name
+                );
+            }
+            continue;
+        }
+        if let Some(group) = groups::disabled_group(mod_id)? {
+            eprintln!(
+                "Skipping addon {}, its group '{}' is disabled",
+                mod_id, group
+            );
+            continue;
+        }
+        let planned = match installed.as_ref().map(|entry| entry.provider.as_str()) {
+            Some(name) if name != DEFAULT_PROVIDER => {
+                cancel
+                    .run(plan_provider_update(name, mod_id, installed.clone(), force))
+                    .await
+            }
+            _ => {
+                cancel
+                    .run(plan_curseforge_update(
+                        mod_id,
+                        installed.clone(),
+                        force,
+                        cancel,
+                    ))
+                    .await
+            }
+        };
+        match planned {
+            Ok(planned) => plan.push(planned),
+            Err(e) => {
+                cancel.check()?;
+                let name = installed
+                    .as_ref()
+                    .map(|entry| entry.name.clone())
+                    .unwrap_or_else(|| mod_id.to_string());
+                failed += 1;
+                plan.push(failed_update(mod_id, name, installed, e.to_string()));
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{} addon(s) could not be checked for updates", failed);
+    }
+
+    Ok(plan)
+}
+
+/// A plan entry for an addon that could not be checked, which leaves it as it is
+fn failed_update(
+    mod_id: u32,
+    name: String,
+    installed: Option<ManifestEntry>,
+    error: String,
+) -> PlannedUpdate {
+    let entry = UpdatePlanEntry {
+        id: mod_id,
+        name,
+        installed_file_id: installed.as_ref().map(|entry| entry.file_id).unwrap_or(0),
+        installed_version: installed
+            .map(|entry| entry.display_name)
+            .unwrap_or_default(),
+        target_file_id: 0,
+        target_version: String::new(),
+        needs_update: false,
+        local_version: String::new(),
+        changelog: String::new(),
+        error,
+    };
+    PlannedUpdate {
+        entry,
+        install: None,
+    }
+}
+
+async fn plan_curseforge_update(
+    mod_id: u32,
+    installed: Option<ManifestEntry>,
+    force: bool,
+    cancel: &CancellationToken,
+) -> Result<PlannedUpdate, Box<dyn std::error::Error>> {
+    let game_mod = curseforge_api::get_mod_info(mod_id).await?;
+    let target_file = game_version::get_mod_file_for_game_version(&game_mod)?;
+    let installed_file_id = installed.as_ref().map(|entry| entry.file_id).unwrap_or(0);
+    let needs_update = force || installed_file_id != target_file.id;
+
+    let mut changelog = String::new();
+    if needs_update {
+        changelog = match changelog_between(&game_mod, installed_file_id, target_file, cancel).await
+        {
+            Ok(changelog) => changelog,
+            Err(e) => {
+                eprintln!("Could not fetch the changelog of {}: {}", game_mod.name, e);
+                String::new()
+            }
+        };
+    }
+
+    let entry = UpdatePlanEntry {
+        id: mod_id,
+        name: game_mod.name.clone(),
+        installed_file_id,
+        installed_version: installed
+            .as_ref()
+            .map(|entry| entry.display_name.clone())
+            .unwrap_or_default(),
+        target_file_id: target_file.id,
+        target_version: target_file.display_name.clone(),
+        needs_update,
+        local_version: installed
+            .as_ref()
+            .and_then(|entry| local_version(&entry.directories))
+            .unwrap_or_default(),
+        changelog,
+        error: String::new(),
+    };
+    Ok(PlannedUpdate {
+        entry,
+        install: Some((
+            curseforge::addon_info(&game_mod),
+            curseforge::addon_file(target_file),
+        )),
+    })
+}
+
+/// Plans the update of an addon from a provider other than CurseForge. Files are compared by
+/// release tag or id where the provider has them and by version otherwise, and no changelog
+/// is shown.
+async fn plan_provider_update(
+    provider: &str,
+    mod_id: u32,
+    installed: Option<ManifestEntry>,
+    force: bool,
+) -> Result<PlannedUpdate, Box<dyn std::error::Error>> {
+    let provider = providers::provider(provider)?;
+    let source_id = installed
+        .as_ref()
+        .map(|entry| entry.provider_id())
+        .unwrap_or_else(|| mod_id.to_string());
+    let info = provider.get_info(&source_id).await?;
+    let target = provider.resolve_download(&source_id).await?;
+    let is_current = installed.as_ref().is_some_and(|entry| {
+        if target.release_tag.is_some() {
+            entry.release_tag == target.release_tag
+        } else if target.id != 0 {
+            entry.file_id == target.id
+        } else {
+            entry.display_name == target.display_name
+        }
+    });
+
+    let entry = UpdatePlanEntry {
+        id: mod_id,
+        name: info.name.clone(),
+        installed_file_id: installed.as_ref().map(|entry| entry.file_id).unwrap_or(0),
+        installed_version: installed
+            .as_ref()
+            .map(|entry| entry.display_name.clone())
+            .unwrap_or_default(),
+        target_file_id: target.id,
+        target_version: target.display_name.clone(),
+        needs_update: force || !is_current,
+        local_version: installed
+            .as_ref()
+            .and_then(|entry| local_version(&entry.directories))
+            .unwrap_or_default(),
+        changelog: String::new(),
+        error: String::new(),
+    };
+    Ok(PlannedUpdate {
+        entry,
+        install: Some((info, target)),
+    })
+}
+
+/// Collects the changelogs of the files newer than the installed one, up to the target file.
+/// Without an installed file only the target's changelog is shown.
+async fn changelog_between(
+    game_mod: &Mod,
+    installed_file_id: u32,
+    target_file: &ModFile,
+    cancel: &CancellationToken,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if installed_file_id == target_file.id {
+        return Ok(String::new());
+    }
+
+    let mut files: Vec<(u32, String)> = Vec::new();
+    if installed_file_id != 0 {
+        files = curseforge_api::get_mod_files_since(game_mod.id, installed_file_id)
+            .await?
+            .into_iter()
+            .filter(|file| file.id <= target_file.id)
+            .filter(|file| {
+                file.id == target_file.id
+                    || file
+                        .game_versions
+                        .iter()
+                        .any(|version| game_version::is_installed_game_version(version))
+            })
+            .map(|file| (file.id, file.display_name))
+            .collect();
+    }
+    if files.is_empty() {
+        files.push((target_file.id, target_file.display_name.clone()));
+    }
+
+    let older = files.len().saturating_sub(MAX_CHANGELOG_FILES);
+    files.truncate(MAX_CHANGELOG_FILES);
+    let mut sections = Vec::new();
+    for (file_id, display_name) in files {
+        cancel.check()?;
+        let changelog = curseforge_api::get_mod_file_changelog(game_mod.id, file_id).await?;
+        sections.push(format!("{}\n{}", display_name, html::to_text(&changelog)));
+    }
+    if older > 0 {
+        sections.push(format!("...and {} older files", older));
+    }
+
+    Ok(sections.join("\n\n"))
+}
+
+/// The version the installed folders of an addon report, preferring their changelog
+fn local_version(directories: &[String]) -> Option<String> {
+    let addons_path = CurseForgeConfig::get().addons_path();
+    directories.iter().find_map(|dir| {
+        let addon_dir = Path::new(&addons_path).join(dir);
+        installed_mods::read_changelog_version(&addon_dir)
+            .or_else(|| installed_mods::read_toc_version(&addon_dir))
+    })
+}
+
+/// What `apply_updates` installed, and the addons whose install failed with the error
+pub struct AppliedUpdates {
+    pub updated: Vec<ManifestEntry>,
+    pub failed: Vec<(String, String)>,
+}
+
+/// Installs every planned update that is needed, stopping between addons if cancelled.
+/// An addon that fails to install is recorded and the rest are still installed.
+pub async fn apply_updates(
+    plan: &[PlannedUpdate],
+    cancel: &CancellationToken,
+) -> Result<AppliedUpdates, Box<dyn std::error::Error>> {
+    let mut applied = AppliedUpdates {
+        updated: Vec::new(),
+        failed: Vec::new(),
+    };
+    for planned in plan.iter().filter(|planned| planned.entry.needs_update) {
+        let Some((info, target)) = &planned.install else {
+            continue;
+        };
+        cancel.check()?;
+        match providers::install_file(info, planned.entry.id, target, cancel).await {
+            Ok(entry) => applied.updated.push(entry),
+            Err(e) => {
+                cancel.check()?;
+                applied
+                    .failed
+                    .push((planned.entry.name.clone(), e.to_string()));
+            }
+        }
+    }
+
+    Ok(applied)
+}
@@ -0,0 +1,258 @@
+use crate::cancellation::CancellationToken;
+use crate::config::CurseForgeConfig;
+use crate::curseforge_api;
+use crate::manifest::now_timestamp;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+const INDEX_FILE: &str = "wtf-backup.json";
+
+/// Limits a WTF backup or restore to some accounts or characters
+#[derive(Debug, Clone, Default)]
+pub struct WtfFilter {
+    pub account: Option<String>,
+    /// Either `Character` or `Realm/Character`
+    pub character: Option<String>,
+}
+
+impl WtfFilter {
+    fn matches_account(&self, account: &str) -> bool {
+        self.account
+            .as_ref()
+            .is_none_or(|filter| filter.eq_ignore_ascii_case(account))
+    }
+
+    fn matches_character(&self, realm: &str, character: &str) -> bool {
+        match &self.character {
+            None => true,
+            Some(filter) => match filter.split_once('/') {
+                Some((filter_realm, filter_character)) => {
+                    filter_realm.eq_ignore_ascii_case(realm)
+                        && filter_character.eq_ignore_ascii_case(character)
+                }
+                None => filter.eq_ignore_ascii_case(character),
+            },
+        }
+    }
+
+    /// Whether a folder from a backup (relative to WTF) is covered by this filter
+    fn matches_unit(&self, unit: &str) -> bool {
+        let parts: Vec<&str> = unit.split('/').collect();
+        match parts.as_slice() {
+            ["Account", account, "SavedVariables"] => {
+                self.matches_account(account) && self.character.is_none()
+            }
+            ["Account", account, realm, character] => {
+                self.matches_account(account) && self.matches_character(realm, character)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Summary stored inside every WTF backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WtfBackupIndex {
+    pub name: String,
+    pub created_at: u64,
+    /// Folders relative to WTF that were archived: account wide `SavedVariables` folders
+    /// and whole character folders
+    pub units: Vec<String>,
+}
+
+pub struct WtfBackup {
+    pub index: WtfBackupIndex,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Archive of a WTF backup. Names are checked so they can't reach outside the backups dir.
+fn backup_path(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(format!("Invalid backup name '{}'", name).into());
+    }
+    Ok(Path::new(&CurseForgeConfig::get().wtf_backups_path()).join(format!("{}.zip", name)))
+}
+
+/// Lists the folders under `WTF/Account` that hold SavedVariables for the filter
+fn find_units(filter: &WtfFilter) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let accounts_path = Path::new(&CurseForgeConfig::get().wtf_path()).join("Account");
+    let mut units = Vec::new();
+    for account in read_dir_names(&accounts_path)? {
+        if !filter.matches_account(&account) {
+            continue;
+        }
+        let account_path = accounts_path.join(&account);
+        for realm in read_dir_names(&account_path)? {
+            if realm == "SavedVariables" {
+                if filter.character.is_none() {
+                    units.push(format!("Account/{}/SavedVariables", account));
+                }
+                continue;
+            }
+            for character in read_dir_names(&account_path.join(&realm))? {
+                if filter.matches_character(&realm, &character) {
+                    units.push(format!("Account/{}/{}/{}", account, realm, character));
+                }
+            }
+        }
+    }
+
+    Ok(units)
+}
+
+/// Folders of the characters matching the filter, as `WTF/Account/<account>/<realm>/<character>`
+pub fn find_characters(filter: &WtfFilter) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let wtf_path = PathBuf::from(CurseForgeConfig::get().wtf_path());
+    Ok(find_units(filter)?
+        .into_iter()
+        .filter(|unit| !unit.ends_with("/SavedVariables"))
+        .map(|unit| wtf_path.join(unit))
+        .collect())
+}
+
+/// Names of the sub folders of `path`, or nothing if it does not exist
+fn read_dir_names(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.path().is_dir() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+
+    Ok(names)
+}
+
+/// Archives account and character SavedVariables matching the filter. `name` defaults to the
+/// current date and time.
+pub fn backup_wtf(
+    name: Option<String>,
+    filter: &WtfFilter,
+) -> Result<WtfBackup, Box<dyn std::error::Error>> {
+    let name = name.unwrap_or_else(|| chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+    let path = backup_path(&name)?;
+    if path.exists() {
+        return Err(format!("WTF backup '{}' already exists", name).into());
+    }
+
+    let units = find_units(filter)?;
+    if units.is_empty() {
+        return Err("No SavedVariables found to back up".into());
+    }
+
+    let index = WtfBackupIndex {
+        name,
+        created_at: now_timestamp(),
+        units,
+    };
+    let index_json = serde_json::to_vec_pretty(&index)?;
+    curseforge_api::zip_dirs(
+        &path,
+        Path::new(&CurseForgeConfig::get().wtf_path()),
+        &index.units,
+        &[(INDEX_FILE, index_json.as_slice())],
+    )?;
+
+    let size = fs::metadata(&path)?.len();
+    Ok(WtfBackup { index, path, size })
+}
+
+/// Lists saved WTF backups, newest first
+pub fn list_wtf_backups() -> Result<Vec<WtfBackup>, Box<dyn std::error::Error>> {
+    let mut backups = Vec::new();
+    let files = match fs::read_dir(CurseForgeConfig::get().wtf_backups_path()) {
+        Ok(files) => files,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(backups),
+        Err(e) => return Err(e.into()),
+    };
+
+    for file in files {
+        let path = file?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("zip") {
+            continue;
+        }
+        if let Ok(index) = read_index(&path) {
+            let size = fs::metadata(&path)?.len();
+            backups.push(WtfBackup { index, path, size });
+        }
+    }
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.index.created_at));
+    Ok(backups)
+}
+
+/// Restores the folders of a WTF backup that match the filter, replacing the current ones.
+/// Everything is extracted before any existing folder is replaced. Returns the restored folders.
+pub fn restore_wtf(
+    name: &str,
+    filter: &WtfFilter,
+    cancel: &CancellationToken,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let path = backup_path(name)?;
+    if !path.is_file() {
+        return Err(format!("WTF backup '{}' not found", name).into());
+    }
+    let index = read_index(&path)?;
+    let units: Vec<String> = index
+        .units
+        .into_iter()
+        .filter(|unit| filter.matches_unit(unit))
+        .collect();
+    if units.is_empty() {
+        return Err(format!(
+            "Nothing in '{}' matches the account or character filter",
+            name
+        )
+        .into());
+    }
+
+    let wtf_path = PathBuf::from(CurseForgeConfig::get().wtf_path());
+    let staging_path = wtf_path.join(".addown-wtf-staging");
+    fs::remove_dir_all(&staging_path).ok();
+    let staged = curseforge_api::unzip_file(
+        &path.to_string_lossy(),
+        &staging_path.to_string_lossy(),
+        cancel,
+    );
+    if let Err(e) = staged {
+        fs::remove_dir_all(&staging_path).ok();
+        return Err(e);
+    }
+
+    // past this point the restore is committed and is not interrupted
+    let mut result = Ok(());
+    for unit in &units {
+        let target = wtf_path.join(unit);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::remove_dir_all(&target).ok();
+        if let Err(e) = fs::rename(staging_path.join(unit), &target) {
+            result = Err(e);
+        }
+    }
+    fs::remove_dir_all(&staging_path).ok();
+    result?;
+
+    Ok(units)
+}
+
+fn read_index(zip_path: &Path) -> Result<WtfBackupIndex, Box<dyn std::error::Error>> {
+    let mut archive = ZipArchive::new(File::open(zip_path)?)?;
+    let mut content = String::new();
+    archive.by_name(INDEX_FILE)?.read_to_string(&mut content)?;
+    Ok(serde_json::from_str(&content)?)
+}